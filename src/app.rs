@@ -1,17 +1,64 @@
 use crate::config::Config;
-use crate::data::{Day, Timecode, TimekeeperData, Week, Year};
-use chrono::Datelike;
-use std::collections::HashMap;
+use crate::data::{
+    date_of, weeks_in_year, Absence, AbsenceKind, Day, Entry, RunningTimer, Timecode,
+    TimekeeperData, Week,
+};
+use crate::duration;
+use crate::export::{self, ExportLayout};
+use crate::hierarchy::{build_rows, Row};
+use crate::history::{Change, Edit, EditKind, History};
+use crate::i18n::I18n;
+use crate::import::{self, OnConflict, Proposal};
+use crate::journal::{self, Journal, JournalEntry};
+use crate::lock::DataLock;
+use crate::persist;
+use crate::report::{Breakdown, Period, Report};
+use crate::schema::DataError;
+use crate::storage::{self, Storage};
+use chrono::{Datelike, Local, NaiveDate};
+use std::collections::HashSet;
 use std::error::Error;
-use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(PartialEq)]
 pub enum State {
     Browsing,
     WritingComment,
     AddingTimecode,
+    // Typing a duration for the active cell, as decimal hours or H:MM
+    EnteringDuration,
+    // Popup listing the time entries of the active cell
+    EditingEntries,
+    // Typing a new or changed entry in the popup
+    EditingEntry,
     ControlScreen,
+    RestoringBackup,
+    // Asking whether to keep edits recovered from the journal
+    Recovering,
+    // Asking whether to continue while another instance holds the lock
+    Locked,
+    // Asking how to handle a data file that was changed by someone else
+    ExternalChange,
+    // Typing a new name for the active timecode
+    RenamingTimecode,
+    // Asking before deleting a timecode with hours from the week
+    ConfirmingDelete,
+    // Typing the kind and hours of an absence on the active day
+    EnteringAbsence,
+    // Screen with hours summed over a range of dates
+    Reports,
+    // Typing the first and last date of a custom report range
+    EnteringReportRange,
+    // Typing the file to export the report range to
+    EnteringExportPath,
+    // Typing the .ics file to import the active week's events from
+    EnteringCalendarPath,
+    // Screen listing calendar events to accept or leave out before importing
+    ReviewingImport,
     Quit,
+    // Quit without saving
+    ForceQuit,
 }
 
 pub struct App {
@@ -21,61 +68,177 @@ pub struct App {
     pub timecodes: Vec<String>,
     // Timecodes that should be shown for every week, regardless of content
     pub starred_timecodes: Vec<String>,
-    // Max 5 timecodes shown at a time; this indicates the range
+    // Lines of the timecode column, with timecodes grouped by prefix
+    pub rows: Vec<Row>,
+    // Max 5 rows shown at a time; this indicates the range
     pub timecode_range: [usize; 2],
-    // Currently highlighted
+    // Currently highlighted row
+    pub active_row: usize,
+    // Index into the week of the highlighted timecode; past the end when a group is highlighted
     pub active_timecode: usize,
+    // Groups folded in the timecode column, by prefix
+    collapsed_groups: HashSet<String>,
     pub active_day: u8,
     pub active_week: u8,
     pub active_year: usize,
     pub state: Vec<State>,
-    pub filepath: String,
+    pub storage: Box<dyn Storage>,
     // String buffer used when adding new timecode
     pub timecode_buffer: String,
+    pub duration_buffer: String,
+    pub absence_buffer: String,
+    // Selected row in the entries popup
+    pub active_entry: usize,
+    // Entry being changed, None when adding a new one
+    editing_entry: Option<usize>,
+    pub entry_buffer: String,
+    // Feedback shown in the info bar
+    pub message: Option<String>,
+    // Backups listed in the restore screen, newest first
+    pub backups: Vec<PathBuf>,
+    pub active_backup: usize,
+    // Shown in the reports screen, None when it's closed
+    pub report: Option<Report>,
+    // Period the reports screen opens with, the one last chosen
+    report_period: Period,
+    pub report_breakdown: Breakdown,
+    // Selected row in the list of days, which scrolls with it
+    pub active_report_day: usize,
+    pub report_buffer: String,
+    pub export_buffer: String,
+    export_layout: ExportLayout,
+    pub calendar_buffer: String,
+    // Calendar events proposed for the active week, shown for review
+    pub proposals: Vec<Proposal>,
+    pub active_proposal: usize,
+    // Events in the week no rule matched
+    pub unmatched_events: usize,
+    // Recurring events left out because their rule isn't supported
+    pub unsupported_events: usize,
+    // The file as it was at launch is backed up on the first save of a session
+    has_backed_up: bool,
+    // Unsaved changes since the last write
+    pub dirty: bool,
+    // Weeks edited since the last write, so storage only has to persist those
+    changed_weeks: HashSet<(usize, u8)>,
+    last_edit: Instant,
+    journal: Journal,
+    // Number of journal entries replayed at launch, awaiting confirmation
+    pub recovered_edits: usize,
+    lock: DataLock,
+    // Description of the instance holding the lock, if it isn't us
+    pub lock_owner: Option<String>,
+    // Modification time of the data file when we last read or wrote it
+    disk_modified: Option<SystemTime>,
+    history: History,
 }
 impl App {
-    pub fn new(filepath: String) -> Result<App, Box<dyn Error>> {
+    /* `storage` is opened read-only when `lock_owner` is set, so nothing is
+     * written until the user chooses to continue without the lock.
+     */
+    pub fn new(
+        mut conf: Config,
+        mut storage: Box<dyn Storage>,
+        lock: DataLock,
+        lock_owner: Option<String>,
+    ) -> Result<App, Box<dyn Error>> {
         let current_date = chrono::Utc::now();
-        let conf = Config::new();
 
-        let timer_js = fs::read_to_string(&filepath);
-        let mut data: TimekeeperData = match timer_js {
-            Ok(t) => serde_json::from_str(&t)?,
-            _ => TimekeeperData(HashMap::<usize, Year>::new()),
+        let mut data: TimekeeperData = storage.load()?;
+
+        // Edits left in the journal were never saved; apply them and ask the user.
+        // While another instance holds the lock, the journal may be theirs
+        let journal = Journal::new(storage.path());
+        let (recovered_edits, changed_weeks) = if lock_owner.is_none() {
+            let pending = journal.read();
+            (pending.len(), journal::replay(&mut data, pending))
+        } else {
+            (0, HashSet::new())
         };
+        let disk_modified = persist::modified(storage.path());
 
+        // Weeks are keyed by ISO week-year, which differs from the calendar year around new year
         let active_week = current_date.iso_week().week() as u8;
-        let active_year = current_date.year() as usize;
+        let active_year = current_date.iso_week().year() as usize;
         let active_day = current_date.weekday().num_days_from_monday() as u8;
         let starred_timecodes = conf.starred_timecodes.clone();
 
+        // Timecodes from before the registry existed are added to it with no details
+        let mut known: Vec<String> = vec![];
+        for (year, week) in data.week_keys() {
+            known.extend(data.get_timecodes(year, week));
+        }
+        conf.register_timecodes(known.iter().map(|tc| tc.as_str()));
+
         data.load_week(
             active_week,
             active_year,
-            starred_timecodes
-                .clone()
+            conf.active_starred_timecodes()
                 .into_iter()
-                .map(|tc| Timecode::from_string(tc))
+                .map(Timecode::from_string)
                 .collect(),
         );
 
         let timecodes = data.get_timecodes(active_year, active_week);
-        let range_end = timecodes.len().min(5);
 
-        Ok(App {
+        let mut app = App {
             data,
             conf,
             timecodes,
             starred_timecodes,
-            timecode_range: [0, range_end],
+            rows: vec![],
+            timecode_range: [0, 0],
+            active_row: 0,
             active_timecode: 0,
+            collapsed_groups: HashSet::new(),
             active_day,
             active_week,
             active_year,
-            state: vec![State::Browsing],
-            filepath,
+            state: {
+                let mut state = vec![State::Browsing];
+                if recovered_edits > 0 {
+                    state.push(State::Recovering);
+                }
+                if lock_owner.is_some() {
+                    state.push(State::Locked);
+                }
+                state
+            },
+            storage,
             timecode_buffer: String::from(""),
-        })
+            duration_buffer: String::from(""),
+            absence_buffer: String::from(""),
+            active_entry: 0,
+            editing_entry: None,
+            entry_buffer: String::from(""),
+            message: None,
+            backups: vec![],
+            active_backup: 0,
+            report: None,
+            report_period: Period::Month,
+            report_breakdown: Breakdown::Timecodes,
+            active_report_day: 0,
+            report_buffer: String::from(""),
+            export_buffer: String::from(""),
+            export_layout: ExportLayout::Long,
+            calendar_buffer: String::from(""),
+            proposals: vec![],
+            active_proposal: 0,
+            unmatched_events: 0,
+            unsupported_events: 0,
+            has_backed_up: false,
+            dirty: recovered_edits > 0,
+            changed_weeks,
+            last_edit: Instant::now(),
+            journal,
+            recovered_edits,
+            lock,
+            lock_owner,
+            disk_modified,
+            history: History::new(),
+        };
+        app.assign_timecodes();
+        Ok(app)
     }
     pub fn get_active_week(&self) -> Option<&Week> {
         Some((self.data.get(self.active_year)?).get(self.active_week))?
@@ -99,22 +262,67 @@ impl App {
     }
 
     pub fn next_timecode(&mut self) {
-        if self.timecodes.len() != 0 && self.active_timecode < self.timecodes.len() - 1 {
-            self.active_timecode += 1;
-            if self.active_timecode >= (self.timecode_range[1]) {
-                self.timecode_range[0] += 1;
-                self.timecode_range[1] += 1;
-            }
+        if self.active_row + 1 < self.rows.len() {
+            self.select_row(self.active_row + 1);
         }
     }
     pub fn prev_timecode(&mut self) {
-        if self.active_timecode > 0 {
-            self.active_timecode -= 1;
-            if self.active_timecode < (self.timecode_range[0]) {
-                self.timecode_range[0] -= 1;
-                self.timecode_range[1] -= 1;
-            }
+        if self.active_row > 0 {
+            self.select_row(self.active_row - 1);
+        }
+    }
+    // Highlights a row, scrolling it into view
+    fn select_row(&mut self, row: usize) {
+        let height = self.rows.len().min(5);
+        if row >= self.timecode_range[1] {
+            self.timecode_range[0] = (row + 1).saturating_sub(height);
+        } else if row < self.timecode_range[0] {
+            self.timecode_range[0] = row;
         }
+        self.timecode_range[0] = self.timecode_range[0].min(self.rows.len() - height);
+        self.timecode_range[1] = self.timecode_range[0] + height;
+        self.active_row = row;
+        self.active_timecode = match self.rows.get(row) {
+            Some(Row::Timecode { idx, .. }) => *idx,
+            _ => self.timecodes.len(),
+        };
+    }
+    // Highlights a timecode, unfolding the groups it is in
+    fn select_timecode(&mut self, idx: usize) {
+        if let Some(code) = self.timecodes.get(idx) {
+            let separator = &self.conf.timecode_separator;
+            self.collapsed_groups
+                .retain(|g| !code.starts_with(&format!("{}{}", g, separator)));
+        }
+        self.active_timecode = idx;
+        self.assign_timecodes();
+    }
+    pub fn on_group_row(&self) -> bool {
+        matches!(self.rows.get(self.active_row), Some(Row::Group { .. }))
+    }
+    /* Folds or unfolds the highlighted group. On a timecode, the group it is in
+     * is folded and highlighted instead.
+     */
+    pub fn toggle_group(&mut self) {
+        let path = match self.rows.get(self.active_row) {
+            Some(Row::Group { path, .. }) => path.clone(),
+            Some(Row::Timecode {
+                group: Some(group), ..
+            }) => group.clone(),
+            _ => return,
+        };
+        if !self.collapsed_groups.remove(&path) {
+            self.collapsed_groups.insert(path.clone());
+        }
+        if let Some(row) = self
+            .rows
+            .iter()
+            .position(|r| matches!(r, Row::Group { path: p, .. } if *p == path))
+        {
+            self.active_row = row;
+        }
+        self.active_timecode = self.timecodes.len();
+        self.assign_timecodes();
     }
     pub fn next_day(&mut self) {
         if self.active_day < 6 {
@@ -133,54 +341,67 @@ impl App {
         }
     }
     pub fn next_week(&mut self) {
-        if self.active_week < 52 {
+        if self.active_week < weeks_in_year(self.active_year) {
             self.active_week += 1;
         } else {
             self.active_week = 1;
             self.next_year();
         }
-        self.data.load_week(
-            self.active_week,
-            self.active_year,
-            self.starred_timecodes
-                .clone()
-                .into_iter()
-                .map(|tc| Timecode::from_string(tc))
-                .collect(),
-        );
-        self.assign_timecodes();
+        self.load_active_week();
     }
     pub fn prev_week(&mut self) {
         if self.active_week > 1 {
             self.active_week -= 1;
         } else {
-            self.active_week = 52;
             self.prev_year();
+            self.active_week = weeks_in_year(self.active_year);
         }
+        self.load_active_week();
+    }
+    // Makes sure the active week exists and shows the starred timecodes
+    pub fn load_active_week(&mut self) {
         self.data.load_week(
             self.active_week,
             self.active_year,
-            self.starred_timecodes
-                .clone()
+            self.conf
+                .active_starred_timecodes()
                 .into_iter()
-                .map(|tc| Timecode::from_string(tc))
+                .map(Timecode::from_string)
                 .collect(),
         );
         self.assign_timecodes();
     }
+    // Rebuilds the rows of the active week, keeping the highlight on the same timecode or group
     pub fn assign_timecodes(&mut self) {
+        let group = match self.rows.get(self.active_row) {
+            Some(Row::Group { path, .. }) if self.active_timecode >= self.timecodes.len() => {
+                Some(path.clone())
+            }
+            _ => None,
+        };
         self.timecodes = self.data.get_timecodes(self.active_year, self.active_week);
-        let len = self.timecodes.len();
-        if len < (self.active_timecode + 1) {
-            self.active_timecode = (len as i32 - 1).max(0) as usize
-        }
-        self.timecode_range[1] = if self.timecodes.len() > 5 && self.timecode_range[1] <= 5 {
+        self.rows = build_rows(
+            self.get_active_week().expect("ERR: Active week not found!"),
+            &self.conf.timecode_separator,
+            &self.collapsed_groups,
+        );
+        let len = self.rows.len();
+        self.timecode_range[1] = if len > 5 && self.timecode_range[1] <= 5 {
             5
         } else {
-            self.timecodes.len()
+            len
         };
-
         self.timecode_range[0] = (self.timecode_range[1] as i32 - 5).max(0) as usize;
+
+        let row = self
+            .rows
+            .iter()
+            .position(|r| match r {
+                Row::Group { path, .. } => Some(path) == group.as_ref(),
+                Row::Timecode { idx, .. } => group.is_none() && *idx == self.active_timecode,
+            })
+            .unwrap_or_else(|| self.active_row.min(len.saturating_sub(1)));
+        self.select_row(row);
     }
     pub fn next_year(&mut self) {
         self.active_year += 1;
@@ -191,18 +412,18 @@ impl App {
     }
 
     pub fn toggle_writing_comment(&mut self) {
-        if self.timecodes.len() == 0 {
+        if self.get_cur_timecode().is_none() {
             return;
         }
         if self.get_state() == &State::Browsing {
             self.state.push(State::WritingComment);
+            // Everything typed until the comment is closed is undone in one step
+            self.history.close_group();
             let day_idx = self.active_day;
-            if let None = self.get_active_day_mut() {
-                let day = Day {
-                    comment: String::from(""),
-                    hours: 0.0,
-                };
-                self.get_active_timecode().unwrap().set_day(day_idx, day)
+            if self.get_active_day_mut().is_none() {
+                let day = Day::new(0);
+                self.get_active_timecode().unwrap().set_day(day_idx, day);
+                self.record_active_day(EditKind::Comment, None);
             }
         } else if self.get_state() == &State::WritingComment {
             self.state.pop();
@@ -212,56 +433,575 @@ impl App {
     pub fn get_state(&self) -> &State {
         self.state.last().unwrap()
     }
-    pub fn change_hours(&mut self, change: f32) {
+    pub fn change_minutes(&mut self, change: i32) {
+        if self.has_entries() {
+            return;
+        }
         let act = self.active_day;
-        match self.get_active_timecode() {
-            Some(t) => match t.get_mut(act) {
+        let before = self.get_active_day().cloned();
+        if let Some(t) = self.get_active_timecode() {
+            match t.get_mut(act) {
                 Some(day) => {
-                    if day.hours >= -change {
-                        day.hours += change
+                    if day.minutes as i32 >= -change {
+                        day.minutes = (day.minutes as i32 + change) as u32
                     }
                 }
                 None => {
-                    let new_day = Day {
-                        hours: change.max(0.0),
-                        comment: String::from(""),
-                    };
+                    let new_day = Day::new(change.max(0) as u32);
                     t.set_day(act, new_day)
                 }
-            },
-            None => (),
+            }
+            self.record_active_day(EditKind::Hours, before);
         }
     }
 
-    pub fn set_hours(&mut self, val: f32) {
+    pub fn set_minutes(&mut self, val: u32) {
+        if self.has_entries() {
+            return;
+        }
         let act = self.active_day;
-        match self.get_active_timecode() {
-            Some(t) => match t.get_mut(act) {
-                Some(day) => day.hours = val,
+        let before = self.get_active_day().cloned();
+        if let Some(t) = self.get_active_timecode() {
+            match t.get_mut(act) {
+                Some(day) => day.minutes = val,
                 None => {
-                    let new_day = Day {
-                        hours: val.max(0.0),
-                        comment: String::from(""),
-                    };
+                    let new_day = Day::new(val);
                     t.set_day(act, new_day)
                 }
-            },
-            None => (),
+            }
+            self.record_active_day(EditKind::Hours, before);
         }
     }
 
-    pub fn write(&self) {
-        fs::write(
-            &self.filepath,
-            serde_json::to_string_pretty(&self.data).expect("ERR: Unable to convert data to JSON!"),
+    // Cells with entries get their hours from the entries, so direct edits are refused
+    fn has_entries(&mut self) -> bool {
+        let has_entries = self
+            .get_active_day()
+            .map(|d| !d.entries.is_empty())
+            .unwrap_or(false);
+        if has_entries {
+            self.message = Some(I18n::hours_from_entries(&self.conf.lang).to_string());
+        }
+        has_entries
+    }
+
+    pub fn toggle_editing_entries(&mut self) {
+        match self.get_state() {
+            State::Browsing if self.get_cur_timecode().is_some() => {
+                self.active_entry = 0;
+                self.state.push(State::EditingEntries);
+            }
+            State::EditingEntries => {
+                self.state.pop();
+            }
+            _ => (),
+        }
+    }
+    pub fn get_active_entries(&self) -> &[Entry] {
+        match self.get_active_day() {
+            Some(d) => &d.entries,
+            None => &[],
+        }
+    }
+    pub fn next_entry(&mut self) {
+        if self.active_entry + 1 < self.get_active_entries().len() {
+            self.active_entry += 1;
+        }
+    }
+    pub fn prev_entry(&mut self) {
+        self.active_entry = self.active_entry.saturating_sub(1);
+    }
+    pub fn start_adding_entry(&mut self) {
+        self.editing_entry = None;
+        self.entry_buffer.clear();
+        self.state.push(State::EditingEntry);
+    }
+    pub fn start_editing_entry(&mut self) {
+        if let Some(e) = self.get_active_entries().get(self.active_entry) {
+            self.entry_buffer = e.to_string().trim_end().to_string();
+            self.editing_entry = Some(self.active_entry);
+            self.state.push(State::EditingEntry);
+        }
+    }
+    pub fn cancel_editing_entry(&mut self) {
+        self.entry_buffer.clear();
+        self.state.pop();
+    }
+    // Parses the buffer as "09:00-10:30 note" and stores it in the active cell
+    pub fn commit_entry(&mut self) {
+        let entry = match Entry::parse(&self.entry_buffer) {
+            Some(e) => e,
+            None => {
+                self.message = Some(format!(
+                    "{}: {}",
+                    I18n::invalid_entry(&self.conf.lang),
+                    self.entry_buffer
+                ));
+                return;
+            }
+        };
+        let act = self.active_day;
+        let editing = self.editing_entry;
+        let before = self.get_active_day().cloned();
+        let tc = match self.get_active_timecode() {
+            Some(tc) => tc,
+            None => return,
+        };
+        if tc.get(act).is_none() {
+            tc.set_day(act, Day::new(0));
+        }
+        let day = tc.get_mut(act).unwrap();
+        match editing {
+            Some(idx) if idx < day.entries.len() => day.entries[idx] = entry,
+            _ => day.entries.push(entry),
+        }
+        day.entries.sort_by_key(|e| e.start);
+        day.sync_minutes();
+        self.record_active_day(EditKind::Entries, before);
+        self.entry_buffer.clear();
+        self.state.pop();
+    }
+    pub fn delete_entry(&mut self) {
+        let act = self.active_day;
+        let idx = self.active_entry;
+        let before = self.get_active_day().cloned();
+        if let Some(day) = self.get_active_timecode().and_then(|tc| tc.get_mut(act)) {
+            if idx < day.entries.len() {
+                day.entries.remove(idx);
+                if day.entries.is_empty() {
+                    day.minutes = 0;
+                }
+                day.sync_minutes();
+                let len = day.entries.len();
+                if self.active_entry >= len {
+                    self.active_entry = len.saturating_sub(1);
+                }
+                self.record_active_day(EditKind::Entries, before);
+            }
+        }
+    }
+
+    pub fn toggle_entering_duration(&mut self) {
+        if self.get_state() == &State::Browsing && self.get_cur_timecode().is_some() {
+            self.duration_buffer.clear();
+            self.state.push(State::EnteringDuration);
+        } else if self.get_state() == &State::EnteringDuration {
+            match duration::parse_duration(&self.duration_buffer) {
+                Some(minutes) => {
+                    self.state.pop();
+                    self.set_minutes(minutes);
+                }
+                None => {
+                    self.message = Some(format!(
+                        "{}: {}",
+                        I18n::invalid_duration(&self.conf.lang),
+                        self.duration_buffer
+                    ))
+                }
+            }
+        }
+    }
+    pub fn cancel_entering_duration(&mut self) {
+        self.duration_buffer.clear();
+        self.state.pop();
+    }
+
+    // Starts the stopwatch on the active cell, stopping one running elsewhere first
+    pub fn start_timer(&mut self) {
+        let timecode = match self.timecodes.get(self.active_timecode) {
+            Some(tc) => tc.clone(),
+            None => return,
+        };
+        let timer = RunningTimer {
+            year: self.active_year,
+            week: self.active_week,
+            timecode,
+            day: self.active_day,
+            started: Local::now(),
+        };
+        if let Some(running) = &self.data.timer {
+            if running.year == timer.year
+                && running.week == timer.week
+                && running.timecode == timer.timecode
+                && running.day == timer.day
+            {
+                return;
+            }
+            self.stop_timer();
+        }
+        let edit = Edit {
+            kind: EditKind::Timer,
+            timecode: timer.timecode.clone(),
+            day: Some(timer.day),
+            changes: vec![Change::Timer {
+                before: None,
+                after: Some(timer),
+            }],
+        };
+        self.apply_edit(edit, false);
+        self.message = Some(I18n::timer_started(&self.conf.lang).to_string());
+    }
+
+    // Adds the elapsed time to the cell the stopwatch was started on
+    pub fn stop_timer(&mut self) {
+        let timer = match self.data.timer.clone() {
+            Some(t) => t,
+            None => return,
+        };
+        let mut minutes = 0;
+        let mut changes = vec![];
+        for (date, before, after) in self.data.clocked_days(&timer, Local::now().naive_local()) {
+            minutes += after
+                .minutes
+                .saturating_sub(before.as_ref().map(|d| d.minutes).unwrap_or(0));
+            changes.push(Change::Day {
+                year: date.iso_week().year() as usize,
+                week: date.iso_week().week() as u8,
+                timecode: timer.timecode.clone(),
+                day: date.weekday().num_days_from_monday() as u8,
+                before,
+                after: Some(after),
+            });
+        }
+        changes.push(Change::Timer {
+            before: Some(timer.clone()),
+            after: None,
+        });
+        let edit = Edit {
+            kind: EditKind::Timer,
+            timecode: timer.timecode.clone(),
+            day: Some(timer.day),
+            changes,
+        };
+        self.apply_edit(edit, false);
+        self.message = Some(format!(
+            "{}: {} {}",
+            I18n::timer_stopped(&self.conf.lang),
+            timer.timecode,
+            duration::format_minutes(minutes, self.conf.duration_format)
+        ));
+    }
+
+    /* Marks the data as changed, journals the new value of the active cell and
+     * adds the change to the undo history. `before` is the cell prior to the edit.
+     */
+    fn record_active_day(&mut self, kind: EditKind, before: Option<Day>) {
+        let after = self.get_active_day().cloned();
+        if after == before {
+            return;
+        }
+        let timecode = self.timecodes[self.active_timecode].clone();
+        let change = Change::Day {
+            year: self.active_year,
+            week: self.active_week,
+            timecode: timecode.clone(),
+            day: self.active_day,
+            before,
+            after,
+        };
+        for entry in change.journal_entries(false) {
+            self.record(entry);
+        }
+        let edit = Edit {
+            kind,
+            timecode,
+            day: Some(self.active_day),
+            changes: vec![change],
+        };
+        self.history.push(edit, kind == EditKind::Comment);
+    }
+
+    // Makes the changes of an edit and adds it to the undo history
+    fn apply_edit(&mut self, edit: Edit, group: bool) {
+        for change in edit.changes.iter() {
+            self.apply_change(change, false);
+        }
+        self.history.push(edit, group);
+    }
+
+    fn apply_change(&mut self, change: &Change, undo: bool) {
+        match change {
+            Change::Starred { before, after } => {
+                let starred = if undo { before } else { after };
+                self.starred_timecodes = starred.clone();
+                self.conf.set_starred_timecodes(starred.clone());
+            }
+            Change::Registry {
+                timecode,
+                before,
+                after,
+            } => {
+                let info = if undo { before } else { after };
+                self.conf.update(|c| {
+                    match info {
+                        Some(info) => c.timecodes.insert(timecode.clone(), info.clone()),
+                        None => c.timecodes.remove(timecode),
+                    };
+                });
+            }
+            change => {
+                for entry in change.journal_entries(undo) {
+                    journal::apply(&mut self.data, &entry);
+                    self.record(entry);
+                }
+            }
+        }
+    }
+
+    pub fn undo(&mut self) {
+        match self.history.take_undo() {
+            Some(edit) => {
+                for change in edit.changes.iter().rev() {
+                    self.apply_change(change, true);
+                }
+                self.focus_edit(&edit);
+                self.message = Some(self.describe_edit(I18n::undone(&self.conf.lang), &edit));
+                self.history.undone(edit);
+            }
+            None => self.message = Some(I18n::nothing_to_undo(&self.conf.lang).to_string()),
+        }
+    }
+
+    pub fn redo(&mut self) {
+        match self.history.take_redo() {
+            Some(edit) => {
+                for change in edit.changes.iter() {
+                    self.apply_change(change, false);
+                }
+                self.focus_edit(&edit);
+                self.message = Some(self.describe_edit(I18n::redone(&self.conf.lang), &edit));
+                self.history.redone(edit);
+            }
+            None => self.message = Some(I18n::nothing_to_redo(&self.conf.lang).to_string()),
+        }
+    }
+
+    // Shows the week and cell an undone or redone edit applied to
+    fn focus_edit(&mut self, edit: &Edit) {
+        let week = edit.changes.iter().find_map(|c| match c {
+            Change::Day { year, week, .. } | Change::AddTimecode { year, week, .. } => {
+                Some((*year, *week))
+            }
+            Change::Absence { date, .. } => Some((
+                date.iso_week().year() as usize,
+                date.iso_week().week() as u8,
+            )),
+            _ => None,
+        });
+        match week {
+            Some((year, week)) if (year, week) != (self.active_year, self.active_week) => {
+                self.active_year = year;
+                self.active_week = week;
+                self.load_active_week();
+            }
+            // Reloading would drop timecodes left empty by the undo
+            _ => self.assign_timecodes(),
+        }
+        if let Some(idx) = self.timecodes.iter().position(|tc| *tc == edit.timecode) {
+            self.select_timecode(idx);
+        }
+        if let Some(day) = edit.day {
+            self.active_day = day;
+        }
+    }
+
+    fn describe_edit(&self, action: &str, edit: &Edit) -> String {
+        let mut text = format!(
+            "{} {}: {}",
+            action,
+            I18n::edit_label(&self.conf.lang, edit.kind),
+            edit.timecode
+        );
+        if let Some(day) = edit.day {
+            text.push(' ');
+            text.push_str(I18n::day_labels(&self.conf.lang)[day as usize]);
+        }
+        text
+    }
+    fn record(&mut self, entry: JournalEntry) {
+        self.dirty = true;
+        self.changed_weeks.extend(entry.week());
+        self.last_edit = Instant::now();
+        if let Err(e) = self.journal.append(&entry) {
+            self.message = Some(format!("{}: {}", I18n::journal_failed(&self.conf.lang), e));
+        }
+    }
+
+    // Saves once no edits have been made for `autosave_secs`
+    pub fn on_tick(&mut self) {
+        if self.dirty
+            && self.conf.autosave_secs > 0
+            && !self.is_prompting()
+            && self.last_edit.elapsed() >= Duration::from_secs(self.conf.autosave_secs)
+        {
+            if self.changed_on_disk() {
+                if self.get_state() == &State::Browsing {
+                    self.state.push(State::ExternalChange);
+                }
+                return;
+            }
+            if let Err(e) = self.save() {
+                self.message = Some(format!("{}: {}", I18n::save_failed(&self.conf.lang), e));
+                // Wait for another idle period before retrying
+                self.last_edit = Instant::now();
+            }
+        }
+    }
+
+    fn is_prompting(&self) -> bool {
+        matches!(
+            self.get_state(),
+            State::Recovering | State::Locked | State::ExternalChange | State::ConfirmingDelete
         )
-        .expect("ERR: Unable to write to file!")
+    }
+
+    pub fn keep_recovered_edits(&mut self) {
+        self.recovered_edits = 0;
+        self.state.pop();
+        self.last_edit = Instant::now();
+    }
+    pub fn discard_recovered_edits(&mut self) {
+        match self.reload() {
+            Ok(()) => {
+                self.recovered_edits = 0;
+                self.state.pop();
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    // Replaces the data in memory with what is in storage, dropping unsaved edits
+    fn reload(&mut self) -> Result<(), DataError> {
+        self.data = self.storage.load()?;
+        self.history.clear();
+        self.disk_modified = persist::modified(self.storage.path());
+        self.changed_weeks.clear();
+        self.load_active_week();
+        self.journal.clear()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    pub fn continue_without_lock(&mut self) {
+        if let Err(e) = self.lock.force_acquire() {
+            self.message = Some(e.to_string());
+        }
+        self.lock_owner = None;
+        self.state.pop();
+        if let Err(e) = self.reopen_storage() {
+            self.message = Some(e.to_string());
+        }
+    }
+
+    /* Opens the storage for writing, which upgrades an older file, and recovers
+     * edits left in the journal. Both were skipped while the lock was held.
+     */
+    fn reopen_storage(&mut self) -> Result<(), DataError> {
+        let dir = self
+            .storage
+            .path()
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+        self.storage = storage::open(self.conf.storage, &dir)?;
+        self.data = self.storage.load()?;
+        self.disk_modified = persist::modified(self.storage.path());
+
+        let pending = self.journal.read();
+        self.recovered_edits = pending.len();
+        self.changed_weeks = journal::replay(&mut self.data, pending);
+        self.dirty = self.recovered_edits > 0;
+        if self.recovered_edits > 0 {
+            self.state.push(State::Recovering);
+        }
+        self.load_active_week();
+        Ok(())
+    }
+
+    fn changed_on_disk(&self) -> bool {
+        persist::modified(self.storage.path()) != self.disk_modified
+    }
+
+    pub fn reload_external_change(&mut self) {
+        match self.reload() {
+            Ok(()) => {
+                self.state.pop();
+                self.message = Some(I18n::reloaded_message(&self.conf.lang).to_string());
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+    /* Loads the file from disk and applies our unsaved edits, taken from the
+     * journal, on top. Cells we edited keep our value, everything else comes
+     * from disk.
+     */
+    pub fn merge_external_change(&mut self) {
+        let mut data = match self.storage.load() {
+            Ok(d) => d,
+            Err(e) => {
+                self.message = Some(e.to_string());
+                return;
+            }
+        };
+        let weeks = journal::replay(&mut data, self.journal.read());
+        self.data = data;
+        self.history.clear();
+        self.changed_weeks.extend(weeks);
+        self.disk_modified = persist::modified(self.storage.path());
+        self.load_active_week();
+        self.state.pop();
+        self.write();
+    }
+    pub fn overwrite_external_change(&mut self) {
+        self.disk_modified = persist::modified(self.storage.path());
+        self.state.pop();
+        self.write();
+    }
+    // Leaves the conflict for later. Also cancels a pending quit, which would only ask again
+    pub fn cancel_external_change(&mut self) {
+        self.state.pop();
+        self.cancel_quit();
+        self.last_edit = Instant::now();
+    }
+
+    // Returns false if the data could not be saved; the reason is shown in the info bar
+    pub fn write(&mut self) -> bool {
+        if self.changed_on_disk() {
+            self.state.push(State::ExternalChange);
+            return false;
+        }
+        match self.save() {
+            Ok(()) => {
+                self.message = Some(I18n::saved_message(&self.conf.lang).to_string());
+                true
+            }
+            Err(e) => {
+                self.message = Some(format!("{}: {}", I18n::save_failed(&self.conf.lang), e));
+                false
+            }
+        }
+    }
+    fn save(&mut self) -> Result<(), DataError> {
+        if !self.has_backed_up {
+            persist::create_backup(self.storage.path(), self.conf.backup_count)?;
+            self.has_backed_up = true;
+        }
+        self.storage.save(&self.data, &self.changed_weeks)?;
+        self.disk_modified = persist::modified(self.storage.path());
+        self.changed_weeks.clear();
+        self.dirty = false;
+        self.journal.clear()?;
+        Ok(())
     }
     pub fn append_char_to_comment(&mut self, c: char) {
+        let before = self.get_active_day().cloned();
         self.get_active_day_mut().unwrap().comment.push(c);
+        self.record_active_day(EditKind::Comment, before);
     }
     pub fn delete_char_from_comment(&mut self) {
+        let before = self.get_active_day().cloned();
         self.get_active_day_mut().unwrap().comment.pop();
+        self.record_active_day(EditKind::Comment, before);
     }
     // XXX: Might be superfluous
     pub fn should_show_cursor(&self) -> bool {
@@ -279,53 +1019,392 @@ impl App {
     pub fn toggle_adding_timecode(&mut self) {
         if self.get_state() == &State::Browsing {
             self.state.push(State::AddingTimecode);
-            self.timecode_range[0] = (self.timecodes.len() as i32 - 4).max(0) as usize;
-            self.timecode_range[1] = self.timecodes.len();
+            // Leaves a free slot below the last row for the input
+            self.timecode_range[0] = (self.rows.len() as i32 - 4).max(0) as usize;
+            self.timecode_range[1] = self.rows.len();
         } else if self.get_state() == &State::AddingTimecode {
-            self.add_timecode(self.timecode_buffer.clone());
+            let timecode = self.timecode_buffer.clone();
+            self.add_timecode(timecode.clone());
             self.flush_timecode_buffer();
             self.state.pop();
-            self.timecode_range[0] = (self.timecodes.len() as i32 - 5).max(0) as usize;
-            self.timecode_range[1] = self.timecodes.len();
-            self.active_timecode = self.timecodes.len() - 1;
+            if let Some(idx) = self.timecodes.iter().position(|tc| *tc == timecode) {
+                self.select_timecode(idx);
+            }
         }
     }
     pub fn cancel_adding_timecode(&mut self) {
         self.flush_timecode_buffer();
         self.state.pop();
-        self.timecode_range[0] = (self.timecodes.len() as i32 - 5).max(0) as usize;
-        self.timecode_range[1] = self.timecodes.len();
-        self.active_timecode = self.timecodes.len() - 1;
+        self.select_row(self.active_row);
     }
     pub fn add_timecode(&mut self, timecode: String) {
         if !self.timecodes.contains(&timecode) {
             self.timecodes.push(timecode.clone());
-            let tc = Timecode::from_string(timecode);
-            self.data
-                .add_timecode(self.active_week, self.active_year, tc);
+            self.conf.register_timecodes(vec![timecode.as_str()]);
+            let edit = Edit {
+                kind: EditKind::NewTimecode,
+                timecode: timecode.clone(),
+                day: None,
+                changes: vec![Change::AddTimecode {
+                    year: self.active_year,
+                    week: self.active_week,
+                    timecode,
+                }],
+            };
+            self.apply_edit(edit, false);
         } else {
             // TODO: Show error message in info box
             // Cannot add timecode with existing name!
-            ()
+        }
+    }
+
+    pub fn toggle_renaming_timecode(&mut self) {
+        if self.get_state() == &State::Browsing {
+            if let Some(tc) = self.get_cur_timecode() {
+                self.timecode_buffer = tc;
+                self.state.push(State::RenamingTimecode);
+            }
+        } else if self.get_state() == &State::RenamingTimecode {
+            let to = self.timecode_buffer.trim().to_string();
+            self.flush_timecode_buffer();
+            self.state.pop();
+            if let Some(from) = self.get_cur_timecode() {
+                if !to.is_empty() && to != from {
+                    self.rename_timecode(&from, &to);
+                }
+            }
+        }
+    }
+    pub fn cancel_renaming_timecode(&mut self) {
+        self.flush_timecode_buffer();
+        self.state.pop();
+    }
+
+    pub fn toggle_entering_absence(&mut self) {
+        if self.get_state() == &State::Browsing {
+            self.state.push(State::EnteringAbsence);
+        } else if self.get_state() == &State::EnteringAbsence {
+            let input = self.absence_buffer.clone();
+            self.absence_buffer.clear();
+            self.state.pop();
+            self.set_absence(&input);
+        }
+    }
+    pub fn cancel_entering_absence(&mut self) {
+        self.absence_buffer.clear();
+        self.state.pop();
+    }
+
+    /* Sets the absence on the active day from input like "v" for a full day of
+     * vacation or "s 3.5" for part of a day sick. Empty input clears it.
+     */
+    fn set_absence(&mut self, input: &str) {
+        let date = match date_of(self.active_year, self.active_week, self.active_day) {
+            Some(d) => d,
+            None => return,
+        };
+        let mut parts = input.split_whitespace();
+        let after = match parts.next() {
+            None => None,
+            Some(kind) => {
+                let minutes = match parts.next() {
+                    Some(hours) => duration::parse_duration(hours),
+                    None => Some(self.conf.norm_minutes()),
+                };
+                match (AbsenceKind::parse(kind), minutes, parts.next()) {
+                    (Some(kind), Some(minutes), None) if minutes > 0 => {
+                        Some(Absence { kind, minutes })
+                    }
+                    _ => {
+                        self.message = Some(I18n::invalid_absence(&self.conf.lang).to_string());
+                        return;
+                    }
+                }
+            }
+        };
+        let before = self.data.absences.get(&date).cloned();
+        if before == after {
+            return;
+        }
+        let kind = match after.as_ref().or(before.as_ref()) {
+            Some(a) => a.kind,
+            None => return,
+        };
+        let edit = Edit {
+            kind: EditKind::Absence,
+            timecode: I18n::absence_label(&self.conf.lang, kind).to_string(),
+            day: Some(self.active_day),
+            changes: vec![Change::Absence {
+                date,
+                before,
+                after,
+            }],
+        };
+        self.apply_edit(edit, false);
+    }
+
+    /* Renames a timecode in every week and in the config. If `to` already exists
+     * the two are merged, adding up days logged on both.
+     */
+    pub fn rename_timecode(&mut self, from: &str, to: &str) {
+        let merged = !self.data.weeks_with(to).is_empty();
+        let snapshot = |data: &TimekeeperData, weeks: &[(usize, u8)]| -> Vec<(usize, u8, Week)> {
+            weeks
+                .iter()
+                .filter_map(|(y, w)| Some((*y, *w, data.get(*y)?.get(*w)?.clone())))
+                .collect()
+        };
+        let timer_before = self.data.timer.clone();
+        let starred_before = self.conf.starred_timecodes.clone();
+        let registry_before = (
+            self.conf.timecode_info(from).cloned(),
+            self.conf.timecode_info(to).cloned(),
+        );
+
+        let weeks = self.data.weeks_with(from);
+        let weeks_before = snapshot(&self.data, &weeks);
+        self.data.rename_timecode(from, to);
+        self.conf.rename_timecode(from, to);
+        self.starred_timecodes = self.conf.starred_timecodes.clone();
+
+        let mut changes = vec![
+            Change::Weeks {
+                before: weeks_before,
+                after: snapshot(&self.data, &weeks),
+            },
+            Change::Starred {
+                before: starred_before,
+                after: self.conf.starred_timecodes.clone(),
+            },
+            Change::Registry {
+                timecode: from.to_string(),
+                before: registry_before.0,
+                after: None,
+            },
+            Change::Registry {
+                timecode: to.to_string(),
+                before: registry_before.1,
+                after: self.conf.timecode_info(to).cloned(),
+            },
+        ];
+        if self.data.timer != timer_before {
+            changes.push(Change::Timer {
+                before: timer_before,
+                after: self.data.timer.clone(),
+            });
+        }
+        for change in changes.iter() {
+            for entry in change.journal_entries(false) {
+                self.record(entry);
+            }
+        }
+        self.history.push(
+            Edit {
+                kind: EditKind::Rename,
+                timecode: to.to_string(),
+                day: None,
+                changes,
+            },
+            false,
+        );
+
+        self.load_active_week();
+        if let Some(idx) = self.timecodes.iter().position(|tc| tc == to) {
+            self.select_timecode(idx);
+        }
+        self.message = Some(I18n::renamed_message(&self.conf.lang, from, to, merged));
+    }
+
+    // Deletes the active timecode from the week, asking first if it has hours
+    pub fn request_delete_timecode(&mut self) {
+        let minutes = match self
+            .get_active_week()
+            .and_then(|w| w.get(self.active_timecode))
+        {
+            Some(tc) => tc.total_minutes(),
+            None => return,
+        };
+        if minutes > 0 {
+            self.state.push(State::ConfirmingDelete);
+        } else {
+            self.delete_timecode();
+        }
+    }
+    pub fn confirm_delete_timecode(&mut self) {
+        self.state.pop();
+        self.delete_timecode();
+    }
+    pub fn cancel_delete_timecode(&mut self) {
+        self.state.pop();
+    }
+
+    /* Removes the active timecode from this week. A starred timecode is unstarred
+     * as well, since it would otherwise be added straight back.
+     */
+    fn delete_timecode(&mut self) {
+        let timecode = match self.get_cur_timecode() {
+            Some(tc) => tc,
+            None => return,
+        };
+        let (year, week) = (self.active_year, self.active_week);
+        let before = self.active_week_snapshot();
+        self.data.week_entry(year, week).remove_timecode(&timecode);
+        let mut changes = vec![Change::Weeks {
+            before,
+            after: self.active_week_snapshot(),
+        }];
+        if self.starred_timecodes.contains(&timecode) {
+            let mut starred = self.starred_timecodes.clone();
+            starred.retain(|tc| tc != &timecode);
+            changes.push(Change::Starred {
+                before: self.starred_timecodes.clone(),
+                after: starred.clone(),
+            });
+            self.starred_timecodes = starred.clone();
+            self.conf.set_starred_timecodes(starred);
+        }
+        let timer = self.data.timer.clone();
+        if let Some(t) = timer.as_ref() {
+            if (t.year, t.week) == (year, week) && t.timecode == timecode {
+                self.data.timer = None;
+                changes.push(Change::Timer {
+                    before: timer,
+                    after: None,
+                });
+            }
+        }
+        for change in changes.iter() {
+            for entry in change.journal_entries(false) {
+                self.record(entry);
+            }
+        }
+        self.history.push(
+            Edit {
+                kind: EditKind::Delete,
+                timecode: timecode.clone(),
+                day: None,
+                changes,
+            },
+            false,
+        );
+        self.assign_timecodes();
+        self.message = Some(I18n::deleted_message(&self.conf.lang, &timecode));
+    }
+
+    /* Moves the active timecode up (-1) or down (1), swapping it with the timecode
+     * on the neighbouring row within the same group. Starred timecodes take on the
+     * new order, so future weeks list them the same way.
+     */
+    pub fn move_timecode(&mut self, offset: i32) {
+        let timecode = match self.get_cur_timecode() {
+            Some(tc) => tc,
+            None => return,
+        };
+        let depth = self.rows[self.active_row].depth();
+        let target = match self.rows.get((self.active_row as i32 + offset) as usize) {
+            Some(Row::Timecode { idx, depth: d, .. }) if *d == depth => *idx,
+            _ => return,
+        };
+        let before = self.active_week_snapshot();
+        let (year, week) = (self.active_year, self.active_week);
+        let moved = self.data.week_entry(year, week).move_timecode(
+            self.active_timecode,
+            target as i32 - self.active_timecode as i32,
+        );
+        if moved.is_none() {
+            return;
+        }
+        let mut changes = vec![Change::Weeks {
+            before,
+            after: self.active_week_snapshot(),
+        }];
+
+        // Follow the row
+        self.active_timecode = target;
+        self.assign_timecodes();
+        let in_week: Vec<String> = self
+            .timecodes
+            .iter()
+            .filter(|tc| self.starred_timecodes.contains(tc))
+            .cloned()
+            .collect();
+        let mut in_week_order = in_week.iter();
+        let starred: Vec<String> = self
+            .starred_timecodes
+            .iter()
+            .map(|tc| {
+                if in_week.contains(tc) {
+                    in_week_order.next().unwrap().clone()
+                } else {
+                    tc.clone()
+                }
+            })
+            .collect();
+        if starred != self.starred_timecodes {
+            changes.push(Change::Starred {
+                before: self.starred_timecodes.clone(),
+                after: starred.clone(),
+            });
+            self.starred_timecodes = starred.clone();
+            self.conf.set_starred_timecodes(starred);
+        }
+
+        for change in changes.iter() {
+            for entry in change.journal_entries(false) {
+                self.record(entry);
+            }
+        }
+        self.history.push(
+            Edit {
+                kind: EditKind::Move,
+                timecode,
+                day: None,
+                changes,
+            },
+            false,
+        );
+    }
+
+    fn active_week_snapshot(&self) -> Vec<(usize, u8, Week)> {
+        match self.get_active_week() {
+            Some(w) => vec![(self.active_year, self.active_week, w.clone())],
+            None => vec![],
         }
     }
 
     pub fn star_timecode(&mut self) {
         if let Some(tc) = self.get_cur_timecode() {
             if !self.starred_timecodes.contains(&tc) {
-                self.starred_timecodes.push(tc.clone());
-                self.conf.add_timecode(tc);
+                let mut after = self.starred_timecodes.clone();
+                after.push(tc.clone());
+                self.change_starred(EditKind::Star, tc, after);
             }
         }
     }
 
     pub fn unstar_timecode(&mut self) {
         if let Some(tc) = self.get_cur_timecode() {
-            self.starred_timecodes.retain(|t| t != &tc);
-            self.conf.remove_timecode(&tc);
+            if self.starred_timecodes.contains(&tc) {
+                let mut after = self.starred_timecodes.clone();
+                after.retain(|t| t != &tc);
+                self.change_starred(EditKind::Unstar, tc, after);
+            }
         }
     }
 
+    fn change_starred(&mut self, kind: EditKind, timecode: String, after: Vec<String>) {
+        let edit = Edit {
+            kind,
+            timecode,
+            day: None,
+            changes: vec![Change::Starred {
+                before: self.starred_timecodes.clone(),
+                after,
+            }],
+        };
+        self.apply_edit(edit, false);
+    }
+
     pub fn get_cur_timecode(&self) -> Option<String> {
         if self.timecodes.len() > self.active_timecode {
             Some(self.timecodes[self.active_timecode].clone())
@@ -338,8 +1417,7 @@ impl App {
         match self.get_state() {
             State::Browsing => {
                 if !self.conf.has_seen_info {
-                    self.conf.has_seen_info = true;
-                    self.conf.write();
+                    self.conf.update(|c| c.has_seen_info = true);
                 }
                 self.state.push(State::ControlScreen);
             }
@@ -353,4 +1431,330 @@ impl App {
     pub fn quit(&mut self) {
         self.state.push(State::Quit);
     }
+    pub fn force_quit(&mut self) {
+        self.state.push(State::ForceQuit);
+    }
+    pub fn cancel_quit(&mut self) {
+        if self.get_state() == &State::Quit {
+            self.state.pop();
+        }
+    }
+
+    pub fn toggle_restoring_backup(&mut self) {
+        match self.get_state() {
+            State::Browsing => {
+                self.backups = persist::list_backups(self.storage.path());
+                self.active_backup = 0;
+                self.state.push(State::RestoringBackup);
+            }
+            State::RestoringBackup => {
+                self.state.pop();
+            }
+            _ => (),
+        };
+    }
+    // Opens the reports screen on the period around the active day
+    pub fn toggle_reports(&mut self) {
+        match self.get_state() {
+            State::Browsing => {
+                let date = date_of(self.active_year, self.active_week, self.active_day)
+                    .unwrap_or_else(|| Local::today().naive_local());
+                let (from, to) = self.report_period.range(date);
+                self.set_report(self.report_period, from, to);
+                self.state.push(State::Reports);
+            }
+            State::Reports => {
+                self.report = None;
+                self.state.pop();
+            }
+            _ => (),
+        };
+    }
+    fn set_report(&mut self, period: Period, from: NaiveDate, to: NaiveDate) {
+        if period != Period::Custom {
+            self.report_period = period;
+        }
+        self.report = Some(Report::new(&self.data, &self.conf, period, from, to));
+        self.active_report_day = 0;
+    }
+    pub fn next_report_period(&mut self) {
+        if let Some(r) = &self.report {
+            let period = r.period.next();
+            let (from, to) = period.range(r.from);
+            self.set_report(period, from, to);
+        }
+    }
+    // Moves the report `steps` periods back or forward
+    pub fn shift_report(&mut self, steps: i32) {
+        if let Some(r) = &self.report {
+            let period = r.period;
+            let (from, to) = period.shift(r.from, r.to, steps);
+            self.set_report(period, from, to);
+        }
+    }
+    pub fn toggle_report_breakdown(&mut self) {
+        self.report_breakdown = match self.report_breakdown {
+            Breakdown::Timecodes => Breakdown::Clients,
+            Breakdown::Clients => Breakdown::Timecodes,
+        };
+    }
+    pub fn next_report_day(&mut self) {
+        let days = self.report.as_ref().map(|r| r.days.len()).unwrap_or(0);
+        if self.active_report_day + 1 < days {
+            self.active_report_day += 1;
+        }
+    }
+    pub fn prev_report_day(&mut self) {
+        self.active_report_day = self.active_report_day.saturating_sub(1);
+    }
+
+    pub fn toggle_entering_report_range(&mut self) {
+        if self.get_state() == &State::Reports {
+            self.state.push(State::EnteringReportRange);
+        } else if self.get_state() == &State::EnteringReportRange {
+            let input = self.report_buffer.clone();
+            self.report_buffer.clear();
+            self.state.pop();
+            // "2026-01-01 2026-03-31", or a single date for one day
+            let dates: Option<Vec<NaiveDate>> = input
+                .split_whitespace()
+                .map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .collect();
+            match dates.as_deref() {
+                Some([day]) => self.set_report(Period::Custom, *day, *day),
+                Some([from, to]) if from <= to => self.set_report(Period::Custom, *from, *to),
+                _ => {
+                    self.message = Some(I18n::invalid_range(&self.conf.lang).to_string());
+                }
+            }
+        }
+    }
+    pub fn cancel_entering_report_range(&mut self) {
+        self.report_buffer.clear();
+        self.state.pop();
+    }
+
+    // Asks where to export the hours in the report range, suggesting a file named after it
+    pub fn start_export(&mut self, layout: ExportLayout) {
+        if let (State::Reports, Some(r)) = (self.get_state(), &self.report) {
+            self.export_buffer = format!("timekeeper_{}_{}.csv", r.from, r.to);
+            self.export_layout = layout;
+            self.state.push(State::EnteringExportPath);
+        }
+    }
+    pub fn confirm_export(&mut self) {
+        let path = PathBuf::from(self.export_buffer.trim());
+        self.cancel_export();
+        let (from, to) = match &self.report {
+            Some(r) => (r.from, r.to),
+            None => return,
+        };
+        self.message = Some(
+            match export::export(&self.data, &self.conf, from, to, self.export_layout, &path) {
+                Ok(rows) => I18n::exported_message(
+                    &self.conf.lang,
+                    rows,
+                    &path.display().to_string(),
+                    export::is_calendar(&path),
+                ),
+                Err(e) => e.to_string(),
+            },
+        );
+    }
+    pub fn cancel_export(&mut self) {
+        self.export_buffer.clear();
+        self.state.pop();
+    }
+
+    // Asks for a calendar file, then lists the active week's events for review
+    pub fn toggle_importing_calendar(&mut self) {
+        if self.get_state() == &State::Browsing {
+            self.calendar_buffer = self.conf.calendar_file.clone().unwrap_or_default();
+            self.state.push(State::EnteringCalendarPath);
+        } else if self.get_state() == &State::EnteringCalendarPath {
+            self.state.pop();
+            let path = PathBuf::from(self.calendar_buffer.trim());
+            let from = match date_of(self.active_year, self.active_week, 0) {
+                Some(d) => d,
+                None => return,
+            };
+            let to = from + chrono::Duration::days(6);
+            let proposed = import::read_ics(&path, from, to).and_then(|(occurrences, skipped)| {
+                import::propose(&self.data, &self.conf, occurrences)
+                    .map(|(proposals, unmatched)| (proposals, unmatched, skipped))
+                    .map_err(|e| e.into())
+            });
+            match proposed {
+                Ok((proposals, _, unsupported)) if proposals.is_empty() => {
+                    let mut message = I18n::no_events(&self.conf.lang).to_string();
+                    if unsupported > 0 {
+                        message.push_str(&format!(
+                            " ({})",
+                            I18n::unsupported_events(&self.conf.lang, unsupported)
+                        ));
+                    }
+                    self.message = Some(message);
+                }
+                Ok((proposals, unmatched, unsupported)) => {
+                    self.proposals = proposals;
+                    self.unmatched_events = unmatched;
+                    self.unsupported_events = unsupported;
+                    self.active_proposal = 0;
+                    self.state.push(State::ReviewingImport);
+                }
+                Err(e) => self.message = Some(e.to_string()),
+            }
+        }
+    }
+    pub fn cancel_importing_calendar(&mut self) {
+        self.calendar_buffer.clear();
+        self.proposals.clear();
+        self.state.pop();
+    }
+    pub fn next_proposal(&mut self) {
+        if self.active_proposal + 1 < self.proposals.len() {
+            self.active_proposal += 1;
+        }
+    }
+    pub fn prev_proposal(&mut self) {
+        self.active_proposal = self.active_proposal.saturating_sub(1);
+    }
+    pub fn toggle_proposal(&mut self) {
+        if let Some(p) = self.proposals.get_mut(self.active_proposal) {
+            p.selected = !p.selected;
+        }
+    }
+    // Selects every proposal, or none if all are selected already
+    pub fn toggle_all_proposals(&mut self) {
+        let select = self.proposals.iter().any(|p| !p.selected);
+        for p in self.proposals.iter_mut() {
+            p.selected = select;
+        }
+    }
+
+    /* Adds the selected events to their days as one undoable edit. Days with time
+     * entries are left as they are, since their hours are the entries' total.
+     */
+    pub fn confirm_import(&mut self) {
+        let rows = import::accepted_rows(&self.proposals);
+        let path = self.calendar_buffer.trim().to_string();
+        self.cancel_importing_calendar();
+        if rows.is_empty() {
+            return;
+        }
+
+        let snapshot = |data: &TimekeeperData, weeks: &[(usize, u8)]| -> Vec<(usize, u8, Week)> {
+            weeks
+                .iter()
+                .map(|(y, w)| {
+                    let week = data.get(*y).and_then(|year| year.get(*w)).cloned();
+                    (*y, *w, week.unwrap_or(Week(vec![])))
+                })
+                .collect()
+        };
+        let mut weeks: Vec<(usize, u8)> = rows
+            .iter()
+            .map(|r| {
+                (
+                    r.date.iso_week().year() as usize,
+                    r.date.iso_week().week() as u8,
+                )
+            })
+            .collect();
+        weeks.sort_unstable();
+        weeks.dedup();
+        let weeks_before = snapshot(&self.data, &weeks);
+        let new_timecodes: Vec<String> = rows
+            .iter()
+            .map(|r| r.timecode.clone())
+            .filter(|tc| self.conf.timecode_info(tc).is_none())
+            .collect();
+
+        let summary = import::merge(&mut self.data, rows, OnConflict::Add);
+        self.conf
+            .register_timecodes(summary.timecodes.iter().map(|tc| tc.as_str()));
+
+        let mut changes = vec![Change::Weeks {
+            before: weeks_before,
+            after: snapshot(&self.data, &weeks),
+        }];
+        for tc in summary
+            .timecodes
+            .iter()
+            .filter(|tc| new_timecodes.contains(tc))
+        {
+            changes.push(Change::Registry {
+                timecode: tc.clone(),
+                before: None,
+                after: self.conf.timecode_info(tc).cloned(),
+            });
+        }
+        for change in changes.iter() {
+            for entry in change.journal_entries(false) {
+                self.record(entry);
+            }
+        }
+        self.history.push(
+            Edit {
+                kind: EditKind::Import,
+                timecode: path,
+                day: None,
+                changes,
+            },
+            false,
+        );
+        self.load_active_week();
+
+        let lang = &self.conf.lang;
+        let mut message = I18n::imported_message(
+            lang,
+            false,
+            summary.days,
+            &duration::format_minutes(summary.minutes, self.conf.duration_format),
+            summary.timecodes.len(),
+        );
+        let skipped = summary.conflicts.iter().filter(|c| c.skipped).count();
+        if skipped > 0 {
+            message.push_str(&I18n::skipped_entry_days(lang, skipped));
+        }
+        self.message = Some(message);
+    }
+
+    pub fn next_backup(&mut self) {
+        if self.active_backup + 1 < self.backups.len() {
+            self.active_backup += 1;
+        }
+    }
+    pub fn prev_backup(&mut self) {
+        self.active_backup = self.active_backup.saturating_sub(1);
+    }
+    // Replaces the current data with the selected backup. The current file is backed up first.
+    pub fn restore_backup(&mut self) {
+        let backup = match self.backups.get(self.active_backup) {
+            Some(b) => b.clone(),
+            None => return,
+        };
+        match storage::load_file(&backup) {
+            Ok(data) => {
+                self.has_backed_up = false;
+                // Weeks missing from the backup must be removed from storage as well
+                self.changed_weeks.extend(self.data.week_keys());
+                self.changed_weeks.extend(data.week_keys());
+                self.data = data;
+                self.history.clear();
+                self.load_active_week();
+                self.toggle_restoring_backup();
+                if self.write() {
+                    self.message = Some(format!(
+                        "{} {}",
+                        I18n::restored_message(&self.conf.lang),
+                        backup.file_name().unwrap_or_default().to_string_lossy()
+                    ));
+                }
+            }
+            Err(e) => {
+                self.message = Some(format!("{}: {}", I18n::restore_failed(&self.conf.lang), e));
+            }
+        }
+    }
 }
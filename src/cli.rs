@@ -0,0 +1,1118 @@
+use crate::clock::{self, ClockFormat};
+use crate::config::{Config, TimecodeInfo};
+use crate::data::{date_of, Absence, AbsenceKind, Day, FlexAdjustment};
+use crate::duration::{format_minutes, parse_duration};
+use crate::export::{self, ExportLayout};
+use crate::flex;
+use crate::i18n::I18n;
+use crate::import::{self, CsvFormat, CsvMapping, OnConflict};
+use crate::journal::Journal;
+use crate::lock::DataLock;
+use crate::report::Period;
+use crate::storage::{self, Storage, StorageKind};
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "Usage:
+    timekeeper                                   Open the timesheet
+    timekeeper log CODE HOURS [-m TEXT] [--date YYYY-MM-DD]
+                                                 Add hours (1.5 or 1:30) to a timecode, today by default
+    timekeeper show [--week N] [--year YYYY]     Show a week, the current one by default
+    timekeeper total [--month YYYY-MM]           Sum hours per timecode, the current month by default
+    timekeeper flex [--date YYYY-MM-DD]          Show the flex balance, up to today by default
+    timekeeper flex adjust HOURS [-m TEXT] [--date YYYY-MM-DD]
+                                                 Add hours to the flex balance, or take them out with -HOURS
+    timekeeper holidays [--year YYYY]            List the holidays taken off, this year by default
+    timekeeper absence KIND|none [HOURS] [--date YYYY-MM-DD] [--to YYYY-MM-DD]
+                                                 Set an absence (vacation, sick, child-sick, parental or unpaid)
+                                                 for a day or the working days up to --to, a full day by default
+    timekeeper absences [--year YYYY]            List absences and the vacation days left, this year by default
+    timekeeper export FILE [--month YYYY-MM] [--from YYYY-MM-DD] [--to YYYY-MM-DD] [--wide]
+                                                 Write hours to a .csv or .xlsx file, one row per timecode and day,
+                                                 or with --wide a grid per week. A .ics file gets a calendar event
+                                                 per timecode and day, and .timeclock (hledger) or .org files get
+                                                 clocked intervals. The current month by default
+    timekeeper import FILE [--format toggl|clockify|csv] [--on-conflict skip|replace|add] [--dry-run]
+                    [--date-column NAME] [--date-format FORMAT] [--hours-column NAME]
+                    [--timecode-column NAME[,NAME]] [--comment-column NAME]
+                                                 Add hours from a CSV file, summed per timecode and day. The format
+                                                 is guessed from the header; days already logged are skipped by default.
+                                                 .timeclock and .org files are read with their time entries
+    timekeeper rename FROM TO                    Rename a timecode everywhere, merging it into TO if that exists
+    timekeeper timecodes                         List the timecode registry
+    timekeeper timecode CODE [--description TEXT] [--client TEXT] [--project TEXT]
+                    [--billable yes|no] [--rate N|none] [--color NAME|#rrggbb|none] [--archived yes|no]
+                                                 Show or change a timecode, adding it if it's new
+    timekeeper help                              Show this message
+
+Add --json to any command for machine-readable output.";
+
+pub enum Command {
+    Log {
+        timecode: String,
+        minutes: u32,
+        comment: Option<String>,
+        date: NaiveDate,
+    },
+    Show {
+        year: usize,
+        week: u8,
+    },
+    Total {
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+    Flex {
+        to: NaiveDate,
+    },
+    FlexAdjust {
+        minutes: i64,
+        comment: String,
+        date: NaiveDate,
+    },
+    Holidays {
+        year: i32,
+    },
+    Absence {
+        // None clears the absence
+        kind: Option<AbsenceKind>,
+        minutes: Option<u32>,
+        from: NaiveDate,
+        to: NaiveDate,
+    },
+    Absences {
+        year: i32,
+    },
+    Export {
+        path: PathBuf,
+        from: NaiveDate,
+        to: NaiveDate,
+        layout: ExportLayout,
+    },
+    Import {
+        path: PathBuf,
+        format: Option<CsvFormat>,
+        mapping: CsvMapping,
+        on_conflict: OnConflict,
+        dry_run: bool,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+    Timecodes,
+    Timecode {
+        timecode: String,
+        update: TimecodeUpdate,
+    },
+    Help,
+}
+
+// Fields to change in a registry entry; None leaves a field as it is
+#[derive(Default)]
+pub struct TimecodeUpdate {
+    description: Option<String>,
+    client: Option<String>,
+    project: Option<String>,
+    billable: Option<bool>,
+    rate: Option<Option<f64>>,
+    color: Option<Option<String>>,
+    archived: Option<bool>,
+}
+
+impl TimecodeUpdate {
+    fn apply(&self, info: &mut TimecodeInfo) {
+        if let Some(d) = &self.description {
+            info.description = d.clone();
+        }
+        if let Some(c) = &self.client {
+            info.client = c.clone();
+        }
+        if let Some(p) = &self.project {
+            info.project = p.clone();
+        }
+        if let Some(b) = self.billable {
+            info.billable = b;
+        }
+        if let Some(r) = self.rate {
+            info.rate = r;
+        }
+        if let Some(c) = &self.color {
+            info.color = c.clone();
+        }
+        if let Some(a) = self.archived {
+            info.archived = a;
+        }
+    }
+}
+
+impl Command {
+    // Whether the command changes the timekeeping data
+    fn writes(&self) -> bool {
+        match self {
+            Command::Log { .. }
+            | Command::FlexAdjust { .. }
+            | Command::Absence { .. }
+            | Command::Rename { .. } => true,
+            Command::Import { dry_run, .. } => !dry_run,
+            _ => false,
+        }
+    }
+}
+
+pub struct Cli {
+    pub command: Command,
+    pub json: bool,
+}
+
+// Parses the arguments after the program name. None means no subcommand, so the TUI is opened
+pub fn parse(args: &[String]) -> Result<Option<Cli>, String> {
+    let (command, rest) = match args.split_first() {
+        Some((c, rest)) => (c.as_str(), rest),
+        None => return Ok(None),
+    };
+
+    let mut json = false;
+    let mut positional = vec![];
+    let mut message = None;
+    let mut date = None;
+    let mut from = None;
+    let mut to = None;
+    let mut wide = false;
+    let mut format = None;
+    let mut mapping = CsvMapping::default();
+    let mut on_conflict = OnConflict::Skip;
+    let mut dry_run = false;
+    let mut week = None;
+    let mut year = None;
+    let mut month = None;
+    let mut update = TimecodeUpdate::default();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--json" => json = true,
+            "-m" | "--message" => message = Some(value()?),
+            "--date" => date = Some(parse_date(&value()?)?),
+            "--from" => from = Some(parse_date(&value()?)?),
+            "--to" => to = Some(parse_date(&value()?)?),
+            "--wide" => wide = true,
+            "--format" => {
+                let v = value()?;
+                format =
+                    Some(CsvFormat::parse(&v).ok_or_else(|| {
+                        format!("Unknown format {}, use toggl, clockify or csv", v)
+                    })?)
+            }
+            "--date-column" => mapping.date = Some(value()?),
+            "--date-format" => mapping.date_format = Some(value()?),
+            "--hours-column" => mapping.duration = Some(value()?),
+            "--timecode-column" => {
+                mapping.timecode = Some(value()?.split(',').map(|c| c.to_string()).collect())
+            }
+            "--comment-column" => mapping.comment = Some(value()?),
+            "--on-conflict" => {
+                let v = value()?;
+                on_conflict = OnConflict::parse(&v).ok_or_else(|| {
+                    format!("Unknown conflict handling {}, use skip, replace or add", v)
+                })?
+            }
+            "--dry-run" => dry_run = true,
+            "--week" => week = Some(parse_number::<u8>(&value()?, "week")?),
+            "--year" => year = Some(parse_number::<usize>(&value()?, "year")?),
+            "--month" => month = Some(parse_month(&value()?)?),
+            "--description" => update.description = Some(value()?),
+            "--client" => update.client = Some(value()?),
+            "--project" => update.project = Some(value()?),
+            "--billable" => update.billable = Some(parse_flag(&value()?)?),
+            "--archived" => update.archived = Some(parse_flag(&value()?)?),
+            "--rate" => {
+                update.rate = Some(match value()?.as_str() {
+                    "none" | "" => None,
+                    v => Some(parse_number::<f64>(v, "rate")?),
+                })
+            }
+            "--color" => {
+                update.color = Some(match value()?.as_str() {
+                    "none" | "" => None,
+                    v => Some(v.to_string()),
+                })
+            }
+            // Negative hours are arguments, not options
+            a if a.starts_with('-') && a.len() > 1 && parse_duration(&a[1..]).is_none() => {
+                return Err(format!("Unknown option {}", a))
+            }
+            a => positional.push(a.to_string()),
+        }
+    }
+
+    let today = Local::today().naive_local();
+    let no_args = |positional: &[String]| match positional.first() {
+        Some(a) => Err(format!("Unexpected argument {}", a)),
+        None => Ok(()),
+    };
+    let command = match command {
+        "log" => match positional.as_slice() {
+            [timecode, hours] => Command::Log {
+                timecode: timecode.clone(),
+                minutes: parse_duration(hours)
+                    .ok_or_else(|| format!("Invalid duration {}, use 1.5 or 1:30", hours))?,
+                comment: message,
+                date: date.unwrap_or(today),
+            },
+            _ => return Err(String::from("log takes a timecode and hours")),
+        },
+        "show" => {
+            no_args(&positional)?;
+            let year = year.unwrap_or(today.iso_week().year() as usize);
+            let week = week.unwrap_or(today.iso_week().week() as u8);
+            if date_of(year, week, 0).is_none() {
+                return Err(format!("{} has no week {}", year, week));
+            }
+            Command::Show { year, week }
+        }
+        "total" => {
+            no_args(&positional)?;
+            let (from, to) = Period::Month.range(month.unwrap_or(today));
+            Command::Total { from, to }
+        }
+        "flex" => match positional.as_slice() {
+            [] => Command::Flex {
+                to: date.unwrap_or(today),
+            },
+            [adjust, hours] if adjust == "adjust" => {
+                let minutes = parse_duration(hours.trim_start_matches('-'))
+                    .ok_or_else(|| format!("Invalid duration {}, use 1.5 or 1:30", hours))?;
+                Command::FlexAdjust {
+                    minutes: if hours.starts_with('-') {
+                        -(minutes as i64)
+                    } else {
+                        minutes as i64
+                    },
+                    comment: message.unwrap_or_default(),
+                    date: date.unwrap_or(today),
+                }
+            }
+            _ => return Err(String::from("flex takes no arguments, or adjust and hours")),
+        },
+        "holidays" => {
+            no_args(&positional)?;
+            Command::Holidays {
+                year: calendar_year(year, today)?,
+            }
+        }
+        "absence" => {
+            let (kind, hours) = match positional.as_slice() {
+                [kind] => (kind, None),
+                [kind, hours] => (kind, Some(hours)),
+                _ => return Err(String::from("absence takes a kind and optional hours")),
+            };
+            let kind = match kind.as_str() {
+                "none" => None,
+                k => Some(AbsenceKind::parse(k).ok_or_else(|| {
+                    format!(
+                        "Unknown absence {}, use vacation, sick, child-sick, parental, unpaid or none",
+                        k
+                    )
+                })?),
+            };
+            let minutes = match hours {
+                Some(h) => Some(
+                    parse_duration(h)
+                        .filter(|m| *m > 0)
+                        .ok_or_else(|| format!("Invalid duration {}, use 1.5 or 1:30", h))?,
+                ),
+                None => None,
+            };
+            let from = date.unwrap_or(today);
+            let to = to.unwrap_or(from);
+            if to < from {
+                return Err(format!("--to {} is before {}", to, from));
+            }
+            Command::Absence {
+                kind,
+                minutes,
+                from,
+                to,
+            }
+        }
+        "absences" => {
+            no_args(&positional)?;
+            Command::Absences {
+                year: calendar_year(year, today)?,
+            }
+        }
+        "export" => match positional.as_slice() {
+            [path] => {
+                let month = Period::Month.range(month.unwrap_or(today));
+                let from = from.unwrap_or(month.0);
+                let to = to.unwrap_or(month.1);
+                if to < from {
+                    return Err(format!("--to {} is before {}", to, from));
+                }
+                Command::Export {
+                    path: PathBuf::from(path),
+                    from,
+                    to,
+                    layout: if wide {
+                        ExportLayout::Wide
+                    } else {
+                        ExportLayout::Long
+                    },
+                }
+            }
+            _ => return Err(String::from("export takes a file name")),
+        },
+        "import" => match positional.as_slice() {
+            [path] => Command::Import {
+                path: PathBuf::from(path),
+                format,
+                mapping,
+                on_conflict,
+                dry_run,
+            },
+            _ => return Err(String::from("import takes a file name")),
+        },
+        "rename" => match positional.as_slice() {
+            [from, to] if from != to => Command::Rename {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            _ => return Err(String::from("rename takes two different timecodes")),
+        },
+        "timecodes" => {
+            no_args(&positional)?;
+            Command::Timecodes
+        }
+        "timecode" => match positional.as_slice() {
+            [timecode] => Command::Timecode {
+                timecode: timecode.clone(),
+                update,
+            },
+            _ => return Err(String::from("timecode takes a timecode")),
+        },
+        "help" | "-h" | "--help" => Command::Help,
+        c => return Err(format!("Unknown command {}", c)),
+    };
+    Ok(Some(Cli { command, json }))
+}
+
+/* Runs a subcommand against the configured storage, printing the result.
+ * The TUI notices the change on disk and offers to reload or merge it.
+ */
+pub fn run(cli: Cli, conf: &mut Config, dir: &Path) -> Result<(), Box<dyn Error>> {
+    match cli.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Command::Timecodes => {
+            print_timecodes(conf, conf.timecodes.keys(), cli.json);
+            return Ok(());
+        }
+        Command::Holidays { year } => {
+            let holidays = conf.calendar.holidays_in(year, &conf.lang);
+            if cli.json {
+                let rows: Vec<Value> = holidays
+                    .iter()
+                    .map(|(date, name)| json!({ "date": date, "name": name }))
+                    .collect();
+                print_json(Value::Array(rows));
+            } else {
+                for (date, name) in holidays {
+                    println!("{}  {}", date, name);
+                }
+            }
+            return Ok(());
+        }
+        Command::Timecode { timecode, update } => {
+            conf.update(|c| {
+                let info = c.timecodes.entry(timecode.clone()).or_default();
+                update.apply(info);
+            });
+            print_timecodes(conf, std::iter::once(&timecode), cli.json);
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    let (mut storage, _lock) = open_storage(conf.storage, dir, cli.command.writes())?;
+    let fmt = conf.duration_format;
+    match cli.command {
+        Command::Log {
+            timecode,
+            minutes,
+            comment,
+            date,
+        } => {
+            let year = date.iso_week().year() as usize;
+            let week = date.iso_week().week() as u8;
+            let day = date.weekday().num_days_from_monday() as u8;
+
+            let mut value = storage
+                .load_week(year, week)?
+                .and_then(|w| w.0.into_iter().find(|tc| tc.timecode == timecode))
+                .and_then(|tc| tc.get(day).cloned())
+                .unwrap_or_else(|| Day::new(0));
+            if !value.entries.is_empty() {
+                return Err(format!(
+                    "{} on {} has time entries, edit them in timekeeper instead",
+                    timecode, date
+                )
+                .into());
+            }
+            value.minutes += minutes;
+            if let Some(c) = comment {
+                if !value.comment.is_empty() {
+                    value.comment.push_str("; ");
+                }
+                value.comment.push_str(&c);
+            }
+            storage.save_day(year, week, &timecode, day, &value)?;
+            conf.register_timecodes(vec![timecode.as_str()]);
+
+            if cli.json {
+                print_json(json!({
+                    "date": date,
+                    "timecode": timecode,
+                    "minutes": value.minutes,
+                    "comment": value.comment,
+                }));
+            } else {
+                println!(
+                    "{} {} {} {} ({}: {})",
+                    I18n::logged_message(&conf.lang),
+                    format_minutes(minutes, fmt),
+                    timecode,
+                    date,
+                    I18n::total_label(&conf.lang),
+                    format_minutes(value.minutes, fmt)
+                );
+            }
+        }
+        Command::Show { year, week } => {
+            let timecodes = storage
+                .load_week(year, week)?
+                .map(|w| w.0)
+                .unwrap_or_default();
+            let day_total = |day: u8| -> u32 {
+                timecodes
+                    .iter()
+                    .filter_map(|tc| tc.get(day))
+                    .map(|d| d.minutes)
+                    .sum()
+            };
+            let week_total: u32 = (0..7).map(day_total).sum();
+
+            if cli.json {
+                let rows: Vec<Value> = timecodes
+                    .iter()
+                    .map(|tc| {
+                        let days: Vec<Value> = (0..7)
+                            .map(|day| match tc.get(day) {
+                                Some(d) => json!({
+                                    "date": date_of(year, week, day),
+                                    "minutes": d.minutes,
+                                    "comment": d.comment,
+                                }),
+                                None => Value::Null,
+                            })
+                            .collect();
+                        let minutes: u32 =
+                            (0..7).filter_map(|d| tc.get(d)).map(|d| d.minutes).sum();
+                        json!({ "timecode": tc.timecode, "days": days, "minutes": minutes })
+                    })
+                    .collect();
+                print_json(json!({
+                    "year": year,
+                    "week": week,
+                    "timecodes": rows,
+                    "minutes": week_total,
+                }));
+                return Ok(());
+            }
+
+            let total = I18n::total_label(&conf.lang);
+            let width = timecodes
+                .iter()
+                .map(|tc| tc.timecode.chars().count())
+                .chain(std::iter::once(total.chars().count()))
+                .max()
+                .unwrap_or(0);
+            let cell = |minutes: Option<u32>| match minutes {
+                Some(m) => format!("{:>7}", format_minutes(m, fmt)),
+                None => format!("{:>7}", ""),
+            };
+
+            println!("{} {}, {}", I18n::week_label(&conf.lang), week, year);
+            let mut header = format!("{:width$}", "", width = width);
+            for label in I18n::day_labels(&conf.lang) {
+                header.push_str(&format!("{:>7}", label.chars().take(3).collect::<String>()));
+            }
+            header.push_str(&format!("{:>7}", total));
+            println!("{}", header);
+            for tc in timecodes.iter() {
+                let mut line = format!("{:width$}", tc.timecode, width = width);
+                for day in 0..7 {
+                    line.push_str(&cell(tc.get(day).map(|d| d.minutes)));
+                }
+                let minutes = (0..7).filter_map(|d| tc.get(d)).map(|d| d.minutes).sum();
+                line.push_str(&cell(Some(minutes)));
+                println!("{}", line);
+            }
+            let mut line = format!("{:width$}", total, width = width);
+            for day in 0..7 {
+                line.push_str(&cell(Some(day_total(day))));
+            }
+            line.push_str(&cell(Some(week_total)));
+            println!("{}", line);
+        }
+        Command::Total { from, to } => {
+            let days = storage.query_range(from, to)?;
+            // Timecodes in the order they were first logged
+            let mut totals: Vec<(String, u32)> = vec![];
+            for d in days.iter() {
+                match totals.iter_mut().find(|(tc, _)| *tc == d.timecode) {
+                    Some((_, minutes)) => *minutes += d.minutes,
+                    None => totals.push((d.timecode.clone(), d.minutes)),
+                }
+            }
+            let sum: u32 = totals.iter().map(|(_, m)| m).sum();
+
+            if cli.json {
+                let rows: Vec<Value> = totals
+                    .iter()
+                    .map(|(tc, m)| json!({ "timecode": tc, "minutes": m }))
+                    .collect();
+                print_json(json!({
+                    "from": from,
+                    "to": to,
+                    "timecodes": rows,
+                    "minutes": sum,
+                    "days": days,
+                }));
+                return Ok(());
+            }
+
+            let total = I18n::total_label(&conf.lang);
+            let width = totals
+                .iter()
+                .map(|(tc, _)| tc.chars().count())
+                .chain(std::iter::once(total.chars().count()))
+                .max()
+                .unwrap_or(0);
+            println!("{} - {}", from, to);
+            for (tc, minutes) in totals.iter() {
+                println!(
+                    "{:width$}{:>8}",
+                    tc,
+                    format_minutes(*minutes, fmt),
+                    width = width
+                );
+            }
+            println!(
+                "{:width$}{:>8}",
+                total,
+                format_minutes(sum, fmt),
+                width = width
+            );
+        }
+        Command::Flex { to } => {
+            let data = storage.load()?;
+            let balance = match flex::balance(&data, conf, to) {
+                Some(b) => b,
+                None => return Err(format!("No hours logged up to {}", to).into()),
+            };
+            let adjustments: Vec<&FlexAdjustment> = data
+                .flex_adjustments
+                .iter()
+                .filter(|a| a.date <= to)
+                .collect();
+
+            if cli.json {
+                let mut value =
+                    serde_json::to_value(&balance).expect("ERR: Unable to convert data to JSON!");
+                value["minutes"] = json!(balance.minutes());
+                value["adjustments"] = json!(adjustments);
+                print_json(value);
+                return Ok(());
+            }
+
+            println!(
+                "{} {} - {}: {}",
+                I18n::flex_label(&conf.lang),
+                balance.from,
+                balance.to,
+                flex::format_balance(balance.minutes(), conf)
+            );
+            for (label, minutes) in [
+                (I18n::logged_label(&conf.lang), balance.logged),
+                (I18n::absent_label(&conf.lang), balance.absent),
+                (I18n::expected_label(&conf.lang), -balance.expected),
+                (I18n::adjusted_label(&conf.lang), balance.adjusted),
+            ] {
+                println!("  {:12}{:>8}", label, flex::format_balance(minutes, conf));
+            }
+            for a in adjustments {
+                let line = format!(
+                    "  {} {:>8} {}",
+                    a.date,
+                    flex::format_balance(a.minutes, conf),
+                    a.comment
+                );
+                println!("{}", line.trim_end());
+            }
+        }
+        Command::FlexAdjust {
+            minutes,
+            comment,
+            date,
+        } => {
+            let mut data = storage.load()?;
+            let adjustment = FlexAdjustment {
+                date,
+                minutes,
+                comment,
+            };
+            data.flex_adjustments.push(adjustment.clone());
+            // No weeks changed, only the adjustments
+            storage.save(&data, &HashSet::new())?;
+
+            if cli.json {
+                print_json(json!(adjustment));
+            } else {
+                println!(
+                    "{} {} {}",
+                    I18n::adjusted_label(&conf.lang),
+                    flex::format_balance(minutes, conf),
+                    date
+                );
+            }
+        }
+        Command::Absence {
+            kind,
+            minutes,
+            from,
+            to,
+        } => {
+            let mut data = storage.load()?;
+            let single = from == to;
+            let mut dates = vec![];
+            let mut date = from;
+            while date <= to {
+                // A range skips weekends and holidays, a single day is set as asked
+                if single || conf.daily_target(date) > 0 {
+                    match kind {
+                        Some(kind) => {
+                            let minutes = minutes.unwrap_or_else(|| conf.norm_minutes());
+                            data.absences.insert(date, Absence { kind, minutes });
+                        }
+                        None => {
+                            data.absences.remove(&date);
+                        }
+                    }
+                    dates.push(date);
+                }
+                date += Duration::days(1);
+            }
+            // No weeks changed, only the absences
+            storage.save(&data, &HashSet::new())?;
+
+            if cli.json {
+                let rows: Vec<Value> = dates
+                    .iter()
+                    .map(|d| json!({ "date": d, "absence": data.absences.get(d) }))
+                    .collect();
+                print_json(Value::Array(rows));
+            } else {
+                for d in dates {
+                    match data.absences.get(&d) {
+                        Some(a) => println!(
+                            "{}  {} {}",
+                            d,
+                            I18n::absence_label(&conf.lang, a.kind),
+                            format_minutes(a.minutes, fmt)
+                        ),
+                        None => println!("{}  -", d),
+                    }
+                }
+            }
+        }
+        Command::Absences { year } => {
+            let data = storage.load()?;
+            let from = NaiveDate::from_ymd(year, 1, 1);
+            let to = NaiveDate::from_ymd(year, 12, 31);
+            let totals = data.absence_totals(from, to);
+            let vacation = totals
+                .iter()
+                .find(|(k, _)| *k == AbsenceKind::Vacation)
+                .map(|(_, m)| *m)
+                .unwrap_or(0);
+            // Vacation is allowed in days, and taken in hours of the daily norm
+            let used = vacation as f64 / conf.norm_minutes().max(1) as f64;
+
+            if cli.json {
+                let days: Vec<Value> = data
+                    .absences
+                    .range(from..=to)
+                    .map(|(d, a)| json!({ "date": d, "kind": a.kind, "minutes": a.minutes }))
+                    .collect();
+                let totals: Vec<Value> = totals
+                    .iter()
+                    .map(|(k, m)| json!({ "kind": k, "minutes": m }))
+                    .collect();
+                print_json(json!({
+                    "year": year,
+                    "absences": days,
+                    "totals": totals,
+                    "vacation_days_used": used,
+                    "vacation_days": conf.vacation_days,
+                }));
+                return Ok(());
+            }
+
+            for (d, a) in data.absences.range(from..=to) {
+                println!(
+                    "{}  {:18}{:>8}",
+                    d,
+                    I18n::absence_label(&conf.lang, a.kind),
+                    format_minutes(a.minutes, fmt)
+                );
+            }
+            for (kind, minutes) in totals.iter().filter(|(_, m)| *m > 0) {
+                println!(
+                    "{:12}{:18}{:>8}",
+                    I18n::total_label(&conf.lang),
+                    I18n::absence_label(&conf.lang, *kind),
+                    format_minutes(*minutes, fmt)
+                );
+            }
+            println!(
+                "{}",
+                I18n::vacation_balance(&conf.lang, used, conf.vacation_days)
+            );
+        }
+        Command::Export {
+            path,
+            from,
+            to,
+            layout,
+        } => {
+            let data = storage.load()?;
+            let rows = export::export(&data, conf, from, to, layout, &path)?;
+            if cli.json {
+                print_json(json!({
+                    "path": path,
+                    "from": from,
+                    "to": to,
+                    "rows": rows,
+                }));
+            } else {
+                println!(
+                    "{}",
+                    I18n::exported_message(
+                        &conf.lang,
+                        rows,
+                        &path.display().to_string(),
+                        export::is_calendar(&path)
+                    )
+                );
+            }
+        }
+        Command::Import {
+            path,
+            format,
+            mapping,
+            on_conflict,
+            dry_run,
+        } => {
+            let is_calendar = path.extension().and_then(|e| e.to_str()) == Some("ics");
+            if is_calendar {
+                return Err(String::from(
+                    "Calendar events are reviewed before importing, press I in timekeeper instead",
+                )
+                .into());
+            }
+            let mut data = storage.load()?;
+            let (format, summary) = match ClockFormat::from_path(&path) {
+                Some(clock_format) => {
+                    let days = clock::read(&path, clock_format, conf)?;
+                    let summary = import::merge_days(&mut data, days, on_conflict);
+                    (clock_format.name(), summary)
+                }
+                None => {
+                    let (format, rows) = import::read_csv(&path, format, mapping, conf)?;
+                    (format.name(), import::merge(&mut data, rows, on_conflict))
+                }
+            };
+            if !dry_run {
+                storage.save(&data, &summary.weeks)?;
+                conf.register_timecodes(summary.timecodes.iter().map(|tc| tc.as_str()));
+            }
+
+            if cli.json {
+                print_json(json!({
+                    "format": format,
+                    "dry_run": dry_run,
+                    "days": summary.days,
+                    "minutes": summary.minutes,
+                    "timecodes": summary.timecodes,
+                    "conflicts": summary.conflicts,
+                }));
+                return Ok(());
+            }
+
+            println!(
+                "{}",
+                I18n::imported_message(
+                    &conf.lang,
+                    dry_run,
+                    summary.days,
+                    &format_minutes(summary.minutes, fmt),
+                    summary.timecodes.len()
+                )
+            );
+            for c in summary.conflicts.iter() {
+                println!(
+                    "  {} {}: {} -> {} ({})",
+                    c.date,
+                    c.timecode,
+                    format_minutes(c.existing, fmt),
+                    format_minutes(c.imported, fmt),
+                    I18n::conflict_label(
+                        &conf.lang,
+                        if c.skipped {
+                            OnConflict::Skip
+                        } else {
+                            on_conflict
+                        }
+                    )
+                );
+            }
+        }
+        Command::Rename { from, to } => {
+            let mut data = storage.load()?;
+            let merged = !data.weeks_with(&to).is_empty();
+            let weeks = data.rename_timecode(&from, &to);
+            if weeks.is_empty() && conf.timecode_info(&from).is_none() {
+                return Err(format!("Unknown timecode {}", from).into());
+            }
+            storage.save(&data, &weeks.iter().cloned().collect())?;
+            conf.rename_timecode(&from, &to);
+
+            if cli.json {
+                print_json(json!({
+                    "from": from,
+                    "to": to,
+                    "merged": merged,
+                    "weeks": weeks.len(),
+                }));
+            } else {
+                println!("{}", I18n::renamed_message(&conf.lang, &from, &to, merged));
+            }
+        }
+        Command::Help
+        | Command::Timecodes
+        | Command::Timecode { .. }
+        | Command::Holidays { .. } => (),
+    }
+    Ok(())
+}
+
+/* Opens the storage for a subcommand, holding the lock while it runs. While the
+ * timesheet has the data open, reading leaves the file as it is, not even
+ * upgrading it. Writing goes ahead, and the timesheet offers to reload the change
+ * before it saves. Edits a crashed session left in the journal have to be
+ * recovered first, or they would be replayed over what is written here.
+ */
+fn open_storage(
+    kind: StorageKind,
+    dir: &Path,
+    writes: bool,
+) -> Result<(Box<dyn Storage>, DataLock), Box<dyn Error>> {
+    let path = storage::data_path(kind, dir);
+    let mut lock = DataLock::new(&path);
+    let storage = match lock.try_acquire()? {
+        Some(owner) if writes => {
+            eprintln!(
+                "timekeeper is open ({}) and will offer to reload this change",
+                owner
+            );
+            storage::open(kind, dir)?
+        }
+        Some(_) => storage::open_read_only(kind, dir)?,
+        None => {
+            if writes && !Journal::new(&path).read().is_empty() {
+                return Err(
+                    "Unsaved edits from a session that didn't exit are waiting to be \
+                            recovered. Open timekeeper to keep or discard them first"
+                        .into(),
+                );
+            }
+            storage::open(kind, dir)?
+        }
+    };
+    Ok((storage, lock))
+}
+
+fn print_timecodes<'a, I: Iterator<Item = &'a String>>(conf: &Config, timecodes: I, json: bool) {
+    let entries: Vec<(&String, TimecodeInfo)> = timecodes
+        .map(|tc| (tc, conf.timecode_info(tc).cloned().unwrap_or_default()))
+        .collect();
+    if json {
+        let rows: Vec<Value> = entries
+            .iter()
+            .map(|(tc, info)| {
+                let mut row =
+                    serde_json::to_value(info).expect("ERR: Unable to convert data to JSON!");
+                row["timecode"] = json!(tc);
+                row
+            })
+            .collect();
+        print_json(Value::Array(rows));
+        return;
+    }
+    for (tc, info) in entries {
+        let mut details = vec![];
+        for (label, value) in [("client", &info.client), ("project", &info.project)] {
+            if !value.is_empty() {
+                details.push(format!("{}: {}", label, value));
+            }
+        }
+        if info.billable {
+            details.push(String::from("billable"));
+        }
+        if let Some(rate) = info.rate {
+            details.push(format!("rate: {}", rate));
+        }
+        if let Some(color) = &info.color {
+            details.push(format!("color: {}", color));
+        }
+        if info.archived {
+            details.push(String::from("archived"));
+        }
+        println!("{}\t{}\t{}", tc, info.description, details.join(", "));
+    }
+}
+
+fn print_json(value: Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&value).expect("ERR: Unable to convert data to JSON!")
+    );
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {}, use YYYY-MM-DD", s))
+}
+
+// "2026-09" as the first day of that month
+fn parse_month(s: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d")
+        .map_err(|_| format!("Invalid month {}, use YYYY-MM", s))
+}
+
+// The year given with --year, or this year. Only years chrono has whole dates for
+fn calendar_year(year: Option<usize>, today: NaiveDate) -> Result<i32, String> {
+    let year = match year {
+        Some(y) => y,
+        None => return Ok(today.year()),
+    };
+    Some(year)
+        .filter(|y| *y <= i32::MAX as usize)
+        .and_then(|y| NaiveDate::from_ymd_opt(y as i32, 12, 31))
+        .map(|d| d.year())
+        .ok_or_else(|| format!("Invalid year {}", year))
+}
+
+fn parse_flag(s: &str) -> Result<bool, String> {
+    match s {
+        "yes" | "true" => Ok(true),
+        "no" | "false" => Ok(false),
+        _ => Err(format!("Invalid value {}, use yes or no", s)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str, what: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("Invalid {} {}", what, s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::journal::JournalEntry;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("timekeeper-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn writes_go_ahead_while_the_timesheet_is_open() {
+        let dir = temp_dir("cli-locked");
+        let path = storage::data_path(StorageKind::Json, &dir);
+        let mut open = DataLock::new(&path);
+        assert!(open.try_acquire().unwrap().is_none());
+
+        let (mut storage, lock) = open_storage(StorageKind::Json, &dir, true).unwrap();
+        storage
+            .save_day(2026, 42, "ACME", 0, &Day::new(60))
+            .unwrap();
+        drop(lock);
+        let week = storage.load_week(2026, 42).unwrap().unwrap();
+        assert_eq!(week.0[0].get(0).map(|d| d.minutes), Some(60));
+        // The lock is still the timesheet's
+        let mut other = DataLock::new(&path);
+        assert!(other.try_acquire().unwrap().is_some());
+
+        drop(open);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reads_leave_the_file_alone_while_the_timesheet_is_open() {
+        let dir = temp_dir("cli-read-only");
+        let path = storage::data_path(StorageKind::Json, &dir);
+        let v4 = r#"{"version": 4, "data": {"years": {}}}"#;
+        fs::write(&path, v4).unwrap();
+        let mut open = DataLock::new(&path);
+        open.try_acquire().unwrap();
+
+        let (mut storage, _lock) = open_storage(StorageKind::Json, &dir, false).unwrap();
+        storage.load().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), v4);
+
+        drop(open);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_wait_for_edits_left_in_the_journal() {
+        let dir = temp_dir("cli-journal");
+        let path = storage::data_path(StorageKind::Json, &dir);
+        let mut journal = Journal::new(&path);
+        journal
+            .append(&JournalEntry::AddTimecode {
+                year: 2026,
+                week: 42,
+                timecode: String::from("ACME"),
+            })
+            .unwrap();
+
+        assert!(open_storage(StorageKind::Json, &dir, true).is_err());
+        assert!(open_storage(StorageKind::Json, &dir, false).is_ok());
+
+        journal.clear().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
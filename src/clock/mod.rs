@@ -0,0 +1,331 @@
+mod org;
+mod timeclock;
+
+use crate::config::Config;
+use crate::data::{DayRecord, Entry};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/* Plain-text formats that record time as clocked intervals. Time entries are
+ * written as they are. Hours logged without entries are laid out one after
+ * another from `export_day_start` and marked, so they come back without entries.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClockFormat {
+    // hledger's timeclock format of i and o lines
+    Timeclock,
+    // CLOCK lines under Org-mode headings
+    Org,
+}
+
+impl ClockFormat {
+    pub fn from_path(path: &Path) -> Option<ClockFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "timeclock" => Some(ClockFormat::Timeclock),
+            "org" => Some(ClockFormat::Org),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClockFormat::Timeclock => "timeclock",
+            ClockFormat::Org => "org",
+        }
+    }
+}
+
+// A stretch of time, as written to or read from a file
+struct Clocked {
+    timecode: String,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    note: String,
+    // Laid out for hours logged without time entries
+    untimed: bool,
+}
+
+// A day's comment, with its date and timecode
+type Comment = (NaiveDate, String, String);
+
+// The days of the file at `path`, with a timecode per account or heading
+pub fn read(
+    path: &Path,
+    format: ClockFormat,
+    conf: &Config,
+) -> Result<Vec<DayRecord>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(parse(&text, format, conf)?)
+}
+
+pub fn parse(text: &str, format: ClockFormat, conf: &Config) -> Result<Vec<DayRecord>, String> {
+    let (clocked, comments) = match format {
+        ClockFormat::Timeclock => timeclock::parse(text, conf)?,
+        ClockFormat::Org => org::parse(text, conf)?,
+    };
+    Ok(collect(clocked, comments))
+}
+
+pub fn write(records: &[DayRecord], format: ClockFormat, conf: &Config) -> String {
+    let clocked = lay_out(records, conf.export_day_start);
+    match format {
+        ClockFormat::Timeclock => timeclock::write(&clocked, records, conf),
+        ClockFormat::Org => org::write(&clocked, records, conf),
+    }
+}
+
+/* An interval per time entry, and one per day without entries starting where
+ * the earlier ones on that date have ended. Ordered by start.
+ */
+fn lay_out(records: &[DayRecord], day_start: NaiveTime) -> Vec<Clocked> {
+    let mut clocked: Vec<Clocked> = vec![];
+    for r in records.iter() {
+        for e in r.entries.iter() {
+            let start = r.date.and_time(e.start);
+            clocked.push(Clocked {
+                timecode: r.timecode.clone(),
+                start,
+                end: start + Duration::minutes(e.minutes() as i64),
+                note: e.note.clone(),
+                untimed: false,
+            });
+        }
+    }
+
+    let mut dates: Vec<NaiveDate> = records.iter().map(|r| r.date).collect();
+    dates.dedup();
+    for date in dates {
+        let untimed: Vec<&DayRecord> = records
+            .iter()
+            .filter(|r| r.date == date && r.entries.is_empty())
+            .collect();
+        let taken: Vec<(NaiveDateTime, NaiveDateTime)> =
+            clocked.iter().map(|c| (c.start, c.end)).collect();
+        let minutes: Vec<u32> = untimed.iter().map(|r| r.minutes).collect();
+        let slots = lay_out_untimed(date.and_time(day_start), &taken, &minutes);
+        for (r, (start, end)) in untimed.into_iter().zip(slots) {
+            clocked.push(Clocked {
+                timecode: r.timecode.clone(),
+                start,
+                end,
+                note: String::new(),
+                untimed: true,
+            });
+        }
+    }
+    clocked.sort_by_key(|c| c.start);
+    clocked
+}
+
+/* Start and end of each of `minutes`, logged without time entries, laid out one
+ * after another from `start`. Each is moved past the `taken` blocks it would
+ * overlap. Shared by every export that has to put such hours on a clock.
+ */
+pub fn lay_out_untimed(
+    start: NaiveDateTime,
+    taken: &[(NaiveDateTime, NaiveDateTime)],
+    minutes: &[u32],
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let mut cursor = start;
+    let mut slots = vec![];
+    for m in minutes {
+        let length = Duration::minutes(*m as i64);
+        while let Some(end) = taken
+            .iter()
+            .filter(|(s, e)| *s < cursor + length && *e > cursor)
+            .map(|(_, e)| *e)
+            .max()
+        {
+            cursor = end;
+        }
+        slots.push((cursor, cursor + length));
+        cursor += length;
+    }
+    slots
+}
+
+/* Turns intervals back into days, by the date they start on. A day gets time
+ * entries unless all its intervals are marked as laid out. Comments are keyed
+ * by date and timecode.
+ */
+fn collect(clocked: Vec<Clocked>, comments: Vec<Comment>) -> Vec<DayRecord> {
+    let mut days: Vec<(DayRecord, bool)> = vec![];
+    let find = |days: &mut Vec<(DayRecord, bool)>, date: NaiveDate, timecode: &str| -> usize {
+        match days
+            .iter()
+            .position(|(d, _)| d.date == date && d.timecode == timecode)
+        {
+            Some(i) => i,
+            None => {
+                days.push((
+                    DayRecord {
+                        date,
+                        timecode: timecode.to_string(),
+                        minutes: 0,
+                        comment: String::new(),
+                        entries: vec![],
+                    },
+                    true,
+                ));
+                days.len() - 1
+            }
+        }
+    };
+    for c in clocked {
+        let i = find(&mut days, c.start.date(), &c.timecode);
+        let (day, untimed) = &mut days[i];
+        *untimed &= c.untimed;
+        day.entries.push(Entry {
+            start: whole_minute(c.start.time()),
+            end: whole_minute(c.end.time()),
+            note: c.note,
+        });
+    }
+    for (date, timecode, comment) in comments {
+        let i = find(&mut days, date, &timecode);
+        let day = &mut days[i].0;
+        if !day.comment.is_empty() {
+            day.comment.push_str("; ");
+        }
+        day.comment.push_str(&comment);
+    }
+
+    let mut records: Vec<DayRecord> = days
+        .into_iter()
+        .map(|(mut day, untimed)| {
+            day.minutes = day.entries.iter().map(|e| e.minutes()).sum();
+            if untimed {
+                day.entries.clear();
+            } else {
+                day.entries.sort_by_key(|e| e.start);
+            }
+            day
+        })
+        .collect();
+    records.sort_by_key(|r| r.date);
+    records
+}
+
+// Timecodes are written as accounts or headings split on the separator
+fn timecode_parts<'a>(timecode: &'a str, conf: &Config) -> Vec<&'a str> {
+    if conf.timecode_separator.is_empty() {
+        vec![timecode]
+    } else {
+        timecode.split(conf.timecode_separator.as_str()).collect()
+    }
+}
+
+fn whole_minute(time: NaiveTime) -> NaiveTime {
+    time.with_second(0).unwrap().with_nanosecond(0).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Day, TimekeeperData};
+    use crate::import::{merge_days, OnConflict};
+    use chrono::Datelike;
+
+    fn entry(start: (u32, u32), end: (u32, u32), note: &str) -> Entry {
+        Entry {
+            start: NaiveTime::from_hms(start.0, start.1, 0),
+            end: NaiveTime::from_hms(end.0, end.1, 0),
+            note: note.to_string(),
+        }
+    }
+
+    fn set(data: &mut TimekeeperData, date: NaiveDate, timecode: &str, day: Day) {
+        data.set_day(
+            date.iso_week().year() as usize,
+            date.iso_week().week() as u8,
+            timecode,
+            date.weekday().num_days_from_monday() as u8,
+            day,
+        );
+    }
+
+    fn with_entries(comment: &str, entries: Vec<Entry>) -> Day {
+        let mut day = Day::new(0);
+        day.comment = comment.to_string();
+        day.entries = entries;
+        day.sync_minutes();
+        day
+    }
+
+    fn sample() -> TimekeeperData {
+        let mut data = TimekeeperData::default();
+        let monday = NaiveDate::from_ymd(2026, 10, 12);
+        let tuesday = NaiveDate::from_ymd(2026, 10, 13);
+        let sunday = NaiveDate::from_ymd(2026, 10, 18);
+
+        // Hours without entries, one laid out around the other's entries
+        let mut day = Day::new(450);
+        day.comment = String::from("Mail, planning; misc");
+        set(&mut data, monday, "ACME/portal/frontend", day);
+        let entries = vec![
+            entry((9, 0), (10, 30), "design review"),
+            entry((13, 15), (14, 0), ""),
+        ];
+        set(&mut data, monday, "ACME/portal", with_entries("", entries));
+        set(&mut data, monday, "Internal", Day::new(30));
+
+        // Running past midnight, and a comment with nothing logged
+        let entries = vec![entry((22, 0), (1, 30), "release")];
+        set(
+            &mut data,
+            tuesday,
+            "ACME/ops",
+            with_entries("On call", entries),
+        );
+        let mut day = Day::new(0);
+        day.comment = String::from("Out of office");
+        set(&mut data, tuesday, "Internal", day);
+
+        set(&mut data, sunday, "ACME", Day::new(75));
+        data
+    }
+
+    fn records(data: &TimekeeperData) -> Vec<DayRecord> {
+        let from = NaiveDate::from_ymd(2026, 1, 1);
+        let to = NaiveDate::from_ymd(2026, 12, 31);
+        let mut records = data.days_between(from, to);
+        records.sort_by(|a, b| (a.date, &a.timecode).cmp(&(b.date, &b.timecode)));
+        records
+    }
+
+    fn round_trip(format: ClockFormat) {
+        let conf = Config::default();
+        let data = sample();
+        let text = write(&records(&data), format, &conf);
+
+        let mut imported = TimekeeperData::default();
+        let days = parse(&text, format, &conf).unwrap();
+        merge_days(&mut imported, days, OnConflict::Skip);
+        assert!(records(&imported) == records(&data), "{}", text);
+
+        // Writing the imported data again gives the same file
+        assert_eq!(write(&records(&imported), format, &conf), text);
+    }
+
+    #[test]
+    fn timeclock_round_trip() {
+        round_trip(ClockFormat::Timeclock);
+    }
+
+    #[test]
+    fn org_round_trip() {
+        round_trip(ClockFormat::Org);
+    }
+
+    #[test]
+    fn untimed_hours_avoid_entries() {
+        let conf = Config::default();
+        let data = sample();
+        let text = write(&records(&data), ClockFormat::Timeclock, &conf);
+        // 7.5 hours from 08:00 would run into the entries, so they start after them
+        assert!(text.contains("i 2026-10-12 14:00:00 ACME:portal:frontend  ; untimed:"));
+        assert!(text.contains("o 2026-10-12 21:30:00"));
+        assert!(text.contains("o 2026-10-14 01:30:00"));
+    }
+}
@@ -0,0 +1,222 @@
+use super::{timecode_parts, Clocked, Comment};
+use crate::config::Config;
+use crate::data::DayRecord;
+use chrono::{NaiveDate, NaiveTime};
+
+// Item after the CLOCK line of hours logged without time entries
+const UNTIMED_ITEM: &str = "- Logged without times";
+const NOTE_ITEM: &str = "- Note taken on ";
+
+/* A heading per part of the timecode, with its intervals in a LOGBOOK drawer.
+ * Entry notes are clock-out notes under their CLOCK line, and the day's comment
+ * is a note dated without a time:
+ *
+ *   * ACME
+ *   ** portal
+ *   :LOGBOOK:
+ *   CLOCK: [2026-10-12 Mon 09:00]--[2026-10-12 Mon 10:30] =>  1:30
+ *   - Note taken on [2026-10-12 Mon 10:30] \\
+ *     design review
+ *   - Note taken on [2026-10-12 Mon] \\
+ *     Day comment
+ *   :END:
+ */
+pub fn write(clocked: &[Clocked], records: &[DayRecord], conf: &Config) -> String {
+    // Sorted by part, so parents come before their children
+    let mut timecodes: Vec<&str> = records.iter().map(|r| r.timecode.as_str()).collect();
+    timecodes.sort_by_key(|tc| timecode_parts(tc, conf));
+    timecodes.dedup();
+
+    let mut text = String::new();
+    let mut written: Vec<&str> = vec![];
+    for timecode in timecodes {
+        let parts = timecode_parts(timecode, conf);
+        // Headings shared with the timecode before are already there
+        let shared = written
+            .iter()
+            .zip(parts.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        for (level, part) in parts.iter().enumerate().skip(shared) {
+            text.push_str(&format!("{} {}\n", "*".repeat(level + 1), part));
+        }
+        written = parts;
+
+        text.push_str(":LOGBOOK:\n");
+        for c in clocked.iter().filter(|c| c.timecode == timecode) {
+            let minutes = (c.end - c.start).num_minutes();
+            text.push_str(&format!(
+                "CLOCK: {}--{} => {:>2}:{:02}\n",
+                timestamp(c.start.date(), Some(c.start.time())),
+                timestamp(c.end.date(), Some(c.end.time())),
+                minutes / 60,
+                minutes % 60
+            ));
+            if c.untimed {
+                text.push_str(UNTIMED_ITEM);
+                text.push('\n');
+            }
+            if !c.note.is_empty() {
+                text.push_str(&note(&timestamp(c.end.date(), Some(c.end.time())), &c.note));
+            }
+        }
+        for r in records.iter().filter(|r| r.timecode == timecode) {
+            if !r.comment.is_empty() {
+                text.push_str(&note(&timestamp(r.date, None), &r.comment));
+            }
+        }
+        text.push_str(":END:\n");
+    }
+    text
+}
+
+/* Reads CLOCK lines under headings, leaving out running clocks. Notes with a
+ * time belong to the CLOCK line above them; notes with only a date are the
+ * comment of that day.
+ */
+pub fn parse(text: &str, conf: &Config) -> Result<(Vec<Clocked>, Vec<Comment>), String> {
+    let mut clocked: Vec<Clocked> = vec![];
+    let mut comments = vec![];
+    let mut headings: Vec<String> = vec![];
+    // Index into `clocked` of the last CLOCK line under the current heading
+    let mut last_clock: Option<usize> = None;
+    // Timestamp of a note whose text is on the lines below it
+    let mut note: Option<(NaiveDate, Option<NaiveTime>, Vec<String>)> = None;
+
+    let lines: Vec<&str> = text.lines().collect();
+    for (i, raw) in lines.iter().enumerate() {
+        let line = raw.trim();
+        let line_no = i + 1;
+
+        // Note text is indented below its item, up to a blank line or the next item
+        if let Some((_, _, text)) = note.as_mut() {
+            let is_text = raw.starts_with(' ') || raw.starts_with('\t');
+            if is_text && !line.is_empty() && !line.starts_with("- ") && !line.starts_with(':') {
+                text.push(line.to_string());
+                continue;
+            }
+            let (date, time, text) = note.take().unwrap();
+            let timecode = timecode(&headings, conf);
+            match (time, last_clock, timecode) {
+                (Some(_), Some(c), _) => clocked[c].note = text.join(" "),
+                (None, _, Some(tc)) => comments.push((date, tc, text.join(" "))),
+                _ => (),
+            }
+        }
+
+        if raw.starts_with('*') {
+            let level = raw.chars().take_while(|c| *c == '*').count();
+            if raw[level..].starts_with(' ') || raw.len() == level {
+                headings.truncate(level - 1);
+                headings.push(heading_title(&raw[level..]));
+                last_clock = None;
+            }
+        } else if let Some(rest) = line.strip_prefix("CLOCK:") {
+            let invalid = || format!("Line {}: invalid CLOCK line {}", line_no, line);
+            // Running clocks have no end yet
+            let (start, rest) = match rest.trim().split_once("--") {
+                Some(split) => split,
+                None => continue,
+            };
+            let end = rest.split("=>").next().unwrap_or("");
+            let start = parse_timestamp(start).ok_or_else(invalid)?;
+            let end = parse_timestamp(end).ok_or_else(invalid)?;
+            let (start, end) = match (start, end) {
+                ((d1, Some(t1)), (d2, Some(t2))) => (d1.and_time(t1), d2.and_time(t2)),
+                _ => return Err(invalid()),
+            };
+            if end < start {
+                return Err(format!("Line {}: clock ends before it starts", line_no));
+            }
+            let timecode = timecode(&headings, conf)
+                .ok_or_else(|| format!("Line {}: CLOCK line outside a heading", line_no))?;
+            clocked.push(Clocked {
+                timecode,
+                start,
+                end,
+                note: String::new(),
+                untimed: false,
+            });
+            last_clock = Some(clocked.len() - 1);
+        } else if line == UNTIMED_ITEM {
+            if let Some(c) = last_clock {
+                clocked[c].untimed = true;
+            }
+        } else if let Some(rest) = line.strip_prefix(NOTE_ITEM) {
+            let stamp = rest.trim_end_matches('\\').trim();
+            if let Some((date, time)) = parse_timestamp(stamp) {
+                note = Some((date, time, vec![]));
+            }
+        }
+    }
+    if let (Some((date, time, text)), Some(tc)) = (note, timecode(&headings, conf)) {
+        match (time, last_clock) {
+            (Some(_), Some(c)) => clocked[c].note = text.join(" "),
+            (None, _) => comments.push((date, tc, text.join(" "))),
+            _ => (),
+        }
+    }
+    Ok((clocked, comments))
+}
+
+fn timecode(headings: &[String], conf: &Config) -> Option<String> {
+    if conf.timecode_separator.is_empty() {
+        headings.last().cloned()
+    } else if headings.is_empty() {
+        None
+    } else {
+        Some(headings.join(&conf.timecode_separator))
+    }
+}
+
+// A heading without its TODO keyword, priority and tags
+fn heading_title(title: &str) -> String {
+    let mut title = title.trim();
+    for keyword in ["TODO ", "DONE "] {
+        title = title.strip_prefix(keyword).unwrap_or(title);
+    }
+    if title.starts_with("[#") && title.get(3..5) == Some("] ") {
+        title = &title[5..];
+    }
+    // Tags are a run of :words: at the end, after a space
+    if let Some(at) = title.rfind(|c: char| c.is_whitespace()) {
+        let tags = &title[at + 1..];
+        if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') {
+            title = title[..at].trim_end();
+        }
+    }
+    title.to_string()
+}
+
+fn note(timestamp: &str, text: &str) -> String {
+    let mut note = format!("{}{} \\\\\n", NOTE_ITEM, timestamp);
+    for line in text.lines() {
+        note.push_str("  ");
+        note.push_str(line);
+        note.push('\n');
+    }
+    note
+}
+
+// An inactive timestamp, like [2026-10-12 Mon 09:00]
+fn timestamp(date: NaiveDate, time: Option<NaiveTime>) -> String {
+    match time {
+        Some(t) => format!("[{} {}]", date.format("%Y-%m-%d %a"), t.format("%H:%M")),
+        None => format!("[{}]", date.format("%Y-%m-%d %a")),
+    }
+}
+
+// The weekday is left out, since Org writes it in the user's language
+fn parse_timestamp(s: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let s = s.trim();
+    let inner = s
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .or_else(|| s.strip_prefix('<').and_then(|s| s.strip_suffix('>')))?;
+    let mut parts = inner.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    let time = parts
+        .filter_map(|p| NaiveTime::parse_from_str(p, "%H:%M").ok())
+        .next();
+    Some((date, time))
+}
@@ -0,0 +1,145 @@
+use super::{timecode_parts, Clocked, Comment};
+use crate::config::Config;
+use crate::data::DayRecord;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::collections::HashSet;
+
+// hledger tag on the i line of hours logged without time entries
+const UNTIMED_TAG: &str = "untimed:";
+
+/* hledger timeclock, with the account from the timecode and the entry's note as
+ * description:
+ *
+ *   ; Day comment, above the day's first interval
+ *   i 2026-10-12 09:00:00 ACME:portal  design review
+ *   o 2026-10-12 10:30:00
+ */
+pub fn write(clocked: &[Clocked], records: &[DayRecord], conf: &Config) -> String {
+    const TIME: &str = "%Y-%m-%d %H:%M:%S";
+    let mut text = String::new();
+    let mut commented: HashSet<(NaiveDate, &str)> = HashSet::new();
+    for c in clocked {
+        let date = c.start.date();
+        if commented.insert((date, c.timecode.as_str())) {
+            let comment = records
+                .iter()
+                .find(|r| r.date == date && r.timecode == c.timecode)
+                .map(|r| r.comment.as_str())
+                .unwrap_or("");
+            if !comment.is_empty() {
+                text.push_str(&format!("; {}\n", comment.replace('\n', " ")));
+            }
+        }
+        let mut line = format!(
+            "i {} {}",
+            c.start.format(TIME),
+            timecode_parts(&c.timecode, conf).join(":")
+        );
+        if !c.note.is_empty() {
+            line.push_str("  ");
+            line.push_str(&c.note);
+        }
+        if c.untimed {
+            line.push_str("  ; ");
+            line.push_str(UNTIMED_TAG);
+        }
+        text.push_str(&line);
+        text.push('\n');
+        text.push_str(&format!("o {}\n", c.end.format(TIME)));
+    }
+    text
+}
+
+/* Reads i and o lines. Comment lines right above an i line are the comment of
+ * its day. Sessions still clocked in at the end of the file are left out.
+ */
+pub fn parse(text: &str, conf: &Config) -> Result<(Vec<Clocked>, Vec<Comment>), String> {
+    let mut clocked = vec![];
+    let mut comments = vec![];
+    let mut pending_comment: Vec<String> = vec![];
+    // Start, timecode, note and whether it's untimed
+    let mut open: Option<(NaiveDateTime, String, String, bool)> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim_end();
+        let invalid = || format!("Line {}: invalid timeclock entry {}", line_no, line);
+
+        if let Some(comment) = line.strip_prefix(';').or_else(|| line.strip_prefix('#')) {
+            pending_comment.push(comment.trim().to_string());
+            continue;
+        }
+        let (code, rest) = match line.split_once(' ') {
+            Some(split) => split,
+            None if line.is_empty() || line.starts_with('*') => {
+                pending_comment.clear();
+                continue;
+            }
+            None => return Err(invalid()),
+        };
+        match code {
+            "i" => {
+                if open.is_some() {
+                    return Err(format!("Line {}: clocked in twice", line_no));
+                }
+                let (time, rest) = parse_time(rest).ok_or_else(invalid)?;
+                let (rest, comment) = match rest.find(" ;").or_else(|| rest.find("\t;")) {
+                    Some(at) => (&rest[..at], rest[at + 2..].trim()),
+                    None => (rest, ""),
+                };
+                // The account ends at two spaces or a tab, and the description follows
+                let rest = rest.trim();
+                let (account, note) = match rest.find("  ").or_else(|| rest.find('\t')) {
+                    Some(at) => (&rest[..at], rest[at..].trim()),
+                    None => (rest, ""),
+                };
+                if account.is_empty() {
+                    return Err(format!("Line {}: no account", line_no));
+                }
+                let timecode = if conf.timecode_separator.is_empty() {
+                    account.to_string()
+                } else {
+                    account.replace(':', &conf.timecode_separator)
+                };
+                let untimed = comment.split(',').any(|tag| tag.trim() == UNTIMED_TAG);
+                if !pending_comment.is_empty() {
+                    comments.push((time.date(), timecode.clone(), pending_comment.join(" ")));
+                    pending_comment.clear();
+                }
+                open = Some((time, timecode, note.to_string(), untimed));
+            }
+            "o" | "O" => {
+                pending_comment.clear();
+                let (end, _) = parse_time(rest).ok_or_else(invalid)?;
+                let (start, timecode, note, untimed) = open
+                    .take()
+                    .ok_or_else(|| format!("Line {}: clocked out without clocking in", line_no))?;
+                if end < start {
+                    return Err(format!("Line {}: clocked out before clocking in", line_no));
+                }
+                clocked.push(Clocked {
+                    timecode,
+                    start,
+                    end,
+                    note,
+                    untimed,
+                });
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok((clocked, comments))
+}
+
+// "2026-10-12 09:00:00", with / or . between the date parts and optional seconds
+fn parse_time(s: &str) -> Option<(NaiveDateTime, &str)> {
+    let s = s.trim_start();
+    let (date, rest) = s.split_once(' ')?;
+    let rest = rest.trim_start();
+    let (time, rest) = rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+    let date = NaiveDate::parse_from_str(&date.replace(['/', '.'], "-"), "%Y-%m-%d").ok()?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()?;
+    Some((date.and_time(time), rest))
+}
@@ -1,45 +1,125 @@
+use crate::data::date_of;
+use crate::duration::DurationFormat;
+use crate::export::ExportColumn;
+use crate::holidays::Calendar;
+use crate::import::CalendarRule;
+use crate::persist::write_atomic;
+use crate::storage::StorageKind;
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     env::{self, consts::OS},
     fs,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-const DEFAULT_UNIX_CONF_PATH: &'static str = "/.config/timekeeper/conf.json";
-const DEFAULT_WINDOWS_CONF_PATH: &'static str = "/timekeeper/conf.json";
+const DEFAULT_UNIX_CONF_PATH: &str = "/.config/timekeeper/conf.json";
+const DEFAULT_WINDOWS_CONF_PATH: &str = "/timekeeper/conf.json";
+const DEFAULT_HOLIDAYS_FILE: &str = "holidays.json";
 
 #[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub starred_timecodes: Vec<String>,
     pub lang: Language,
     pub has_seen_info: bool,
+    // Number of timestamped copies of the data file kept in backups/
+    pub backup_count: usize,
+    // Seconds without edits before unsaved changes are written. 0 disables autosave
+    pub autosave_secs: u64,
+    // Backend used for timekeeping data
+    pub storage: StorageKind,
+    // How durations are shown: decimal hours or H:MM
+    pub duration_format: DurationFormat,
+    // What each timecode means, keyed by timecode
+    pub timecodes: BTreeMap<String, TimecodeInfo>,
+    // Splits timecodes like "ACME/portal/frontend" into groups. Empty shows them flat
+    pub timecode_separator: String,
+    // Hours expected on each weekday, Monday to Friday. 0 turns the daily comparison off
+    pub daily_target_hours: f64,
+    // Hours expected in a week. 0 turns the weekly comparison off
+    pub weekly_target_hours: f64,
+    // First day counted in the flex balance. When unset, the first day with hours logged
+    pub flex_start: Option<NaiveDate>,
+    // Countries whose public holidays are days off, e.g. ["NO"]
+    pub holiday_countries: Vec<String>,
+    // JSON file with extra holidays. When unset, holidays.json next to this file is used if present
+    pub holidays_file: Option<String>,
+    // Vacation days allowed per year
+    pub vacation_days: f64,
+    // Columns of the long export format, in order
+    pub export_columns: Vec<ExportColumn>,
+    // chrono format string for dates in exports
+    pub export_date_format: String,
+    // Field separator in CSV exports
+    pub export_delimiter: char,
+    // Where hours without time entries start in calendar exports, laid out one after another
+    #[serde(with = "crate::data::hhmm")]
+    pub export_day_start: NaiveTime,
+    // Which timecode calendar events are logged on, the first matching rule winning
+    pub calendar_rules: Vec<CalendarRule>,
+    // .ics file suggested when importing a calendar
+    pub calendar_file: Option<String>,
+    #[serde(skip)]
+    pub calendar: Calendar,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            lang: Language::English,
+            starred_timecodes: vec![],
+            has_seen_info: false,
+            backup_count: 10,
+            autosave_secs: 30,
+            storage: StorageKind::Json,
+            duration_format: DurationFormat::Decimal,
+            timecodes: BTreeMap::new(),
+            timecode_separator: String::from("/"),
+            daily_target_hours: 7.5,
+            weekly_target_hours: 37.5,
+            flex_start: None,
+            holiday_countries: vec![],
+            holidays_file: None,
+            vacation_days: 25.0,
+            export_columns: vec![
+                ExportColumn::Date,
+                ExportColumn::Timecode,
+                ExportColumn::Hours,
+                ExportColumn::Comment,
+            ],
+            export_date_format: String::from("%Y-%m-%d"),
+            export_delimiter: ',',
+            export_day_start: NaiveTime::from_hms(8, 0, 0),
+            calendar_rules: vec![],
+            calendar_file: None,
+            calendar: Calendar::default(),
+        }
+    }
 }
 impl Config {
-    pub fn new() -> Self {
+    // Loads the config file, writing the defaults if there is none
+    pub fn new() -> Result<Self, String> {
         let filepath_str = Config::get_filepath();
         let filepath = Path::new(&filepath_str);
         let prefix = filepath.parent().unwrap();
         std::fs::create_dir_all(prefix).unwrap();
 
-        let config_js = fs::read_to_string(&filepath);
-        match config_js {
+        let config_js = fs::read_to_string(filepath);
+        let mut conf: Config = match config_js {
             Ok(c) => serde_json::from_str(&c).expect("ERR: config file corrupted!"),
             _ => {
-                // TODO: When this expands, impl default for config
-                let new_conf = Config {
-                    lang: Language::English,
-                    starred_timecodes: vec![],
-                    has_seen_info: false,
-                };
-                fs::write(
-                    &filepath,
-                    serde_json::to_string_pretty(&new_conf)
-                        .expect("ERR: Unable to write config file!"),
-                )
-                .expect("ERR: Unable to write to file!");
+                let new_conf = Config::default();
+                new_conf.write();
                 new_conf
             }
-        }
+        };
+        let holidays_file = match &conf.holidays_file {
+            Some(f) => Some(PathBuf::from(f)),
+            None => Some(prefix.join(DEFAULT_HOLIDAYS_FILE)).filter(|p| p.exists()),
+        };
+        conf.calendar = Calendar::new(&conf.holiday_countries, holidays_file.as_deref())?;
+        Ok(conf)
     }
     pub fn get_filepath() -> String {
         match OS {
@@ -58,27 +138,164 @@ impl Config {
         }
     }
 
-    pub fn add_timecode(&mut self, timecode: String) {
-        if !self.starred_timecodes.contains(&timecode) {
-            self.starred_timecodes.push(timecode);
-            self.write();
+    pub fn set_starred_timecodes(&mut self, timecodes: Vec<String>) {
+        self.update(|c| c.starred_timecodes = timecodes.clone());
+    }
+    // Starred timecodes that are added to new weeks, leaving out archived ones
+    pub fn active_starred_timecodes(&self) -> Vec<String> {
+        self.starred_timecodes
+            .iter()
+            .filter(|tc| !self.timecode_info(tc).map(|i| i.archived).unwrap_or(false))
+            .cloned()
+            .collect()
+    }
+    // Renames in the starred timecodes and the registry, keeping the entry of `to` if it has one
+    pub fn rename_timecode(&mut self, from: &str, to: &str) {
+        self.update(|c| {
+            let mut starred = vec![];
+            for tc in c.starred_timecodes.iter() {
+                let tc = if tc == from {
+                    to.to_string()
+                } else {
+                    tc.clone()
+                };
+                if !starred.contains(&tc) {
+                    starred.push(tc);
+                }
+            }
+            c.starred_timecodes = starred;
+            if let Some(info) = c.timecodes.remove(from) {
+                c.timecodes.entry(to.to_string()).or_insert(info);
+            }
+        });
+    }
+    pub fn timecode_info(&self, timecode: &str) -> Option<&TimecodeInfo> {
+        self.timecodes.get(timecode)
+    }
+    // Adds the timecodes that aren't in the registry yet, writing the config if any were new
+    pub fn register_timecodes<'a, I: IntoIterator<Item = &'a str>>(&mut self, timecodes: I) {
+        let new: Vec<&str> = timecodes
+            .into_iter()
+            .filter(|tc| !self.timecodes.contains_key(*tc))
+            .collect();
+        if !new.is_empty() {
+            self.update(|c| {
+                for tc in new.iter() {
+                    c.timecodes.entry(tc.to_string()).or_default();
+                }
+            });
+        }
+    }
+    // Minutes in a normal working day
+    pub fn norm_minutes(&self) -> u32 {
+        (self.daily_target_hours.max(0.0) * 60.0).round() as u32
+    }
+    pub fn holiday(&self, date: NaiveDate) -> Option<String> {
+        self.calendar.holiday(date, &self.lang)
+    }
+    // Minutes expected on a date; weekends and holidays have no target
+    pub fn daily_target(&self, date: NaiveDate) -> u32 {
+        match date.weekday() {
+            Weekday::Sat | Weekday::Sun => 0,
+            _ if self.holiday(date).is_some() => 0,
+            _ => self.norm_minutes(),
         }
     }
-    pub fn remove_timecode(&mut self, timecode: &String) {
-        self.starred_timecodes.retain(|tc| tc != timecode);
-        self.write();
+    // Minutes expected in a week, less a normal day for each holiday on a weekday
+    pub fn weekly_target(&self, year: usize, week: u8) -> u32 {
+        let target = (self.weekly_target_hours.max(0.0) * 60.0).round() as u32;
+        let holidays = (0..5)
+            .filter_map(|day| date_of(year, week, day))
+            .filter(|date| self.holiday(*date).is_some())
+            .count() as u32;
+        target.saturating_sub(holidays * self.norm_minutes())
     }
-    pub fn write(&self) {
-        fs::write(
-            Config::get_filepath(),
-            serde_json::to_string_pretty(&self).expect("ERR: Unable to convert data to JSON!"),
+    /* Makes `change` here and in the config file, as the file is now. The CLI
+     * and other instances write the file too, so only the change is written
+     * over what they left there. The registry and starred timecodes are then
+     * taken from the file, picking up what the others added.
+     */
+    pub fn update<F: Fn(&mut Config)>(&mut self, change: F) {
+        self.update_file(Path::new(&Config::get_filepath()), change)
+    }
+    fn update_file<F: Fn(&mut Config)>(&mut self, path: &Path, change: F) {
+        let saved = fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str::<Config>(&c).ok());
+        change(self);
+        match saved {
+            Some(mut saved) => {
+                change(&mut saved);
+                saved.write_to(path);
+                self.starred_timecodes = saved.starred_timecodes;
+                self.timecodes = saved.timecodes;
+            }
+            None => self.write_to(path),
+        }
+    }
+    fn write(&self) {
+        self.write_to(Path::new(&Config::get_filepath()))
+    }
+    fn write_to(&self, path: &Path) {
+        write_atomic(
+            path,
+            serde_json::to_string_pretty(&self)
+                .expect("ERR: Unable to convert data to JSON!")
+                .as_bytes(),
         )
         .expect("ERR: Unable to write to file!")
     }
 }
 
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct TimecodeInfo {
+    pub description: String,
+    pub client: String,
+    pub project: String,
+    pub billable: bool,
+    // Per hour, in whatever currency is invoiced
+    pub rate: Option<f64>,
+    // A color name such as "blue", or "#rrggbb"
+    pub color: Option<String>,
+    // Archived timecodes are kept for history but no longer added to new weeks
+    pub archived: bool,
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum Language {
     Norsk,
     English,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_keep_what_others_wrote_to_the_file() {
+        let dir = std::env::temp_dir().join(format!("timekeeper-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("conf.json");
+        let mut tui = Config::default();
+        tui.write_to(&path);
+
+        // The CLI registers a timecode while the TUI is open
+        let mut cli: Config = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        cli.update_file(&path, |c| {
+            c.timecodes.entry(String::from("ACME")).or_default();
+        });
+
+        tui.update_file(&path, |c| {
+            let info = c.timecodes.entry(String::from("Internal")).or_default();
+            info.billable = true;
+        });
+        let saved: Config = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        for conf in [&tui, &saved] {
+            assert!(conf.timecodes.contains_key("ACME"));
+            assert!(conf.timecodes["Internal"].billable);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,113 @@
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
-#[derive(Serialize, Deserialize)]
-pub struct TimekeeperData(pub HashMap<usize, Year>);
+// Calendar date of a weekday (0 = Monday) in an ISO week
+pub fn date_of(year: usize, week: u8, day: u8) -> Option<NaiveDate> {
+    NaiveDate::from_isoywd_opt(year as i32, week as u32, Weekday::Mon)
+        .map(|monday| monday + Duration::days(day as i64))
+}
+
+// ISO years have 53 weeks when they start on a Thursday, or a Wednesday in leap years
+pub fn weeks_in_year(year: usize) -> u8 {
+    if NaiveDate::from_isoywd_opt(year as i32, 53, Weekday::Mon).is_some() {
+        53
+    } else {
+        52
+    }
+}
+
+// A single logged day, flattened out of the Year/Week/Timecode structure
+#[derive(Serialize, PartialEq)]
+pub struct DayRecord {
+    pub date: NaiveDate,
+    pub timecode: String,
+    pub minutes: u32,
+    pub comment: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<Entry>,
+}
+
+// A change to the flex balance outside the timesheet, such as paid out overtime or a correction
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FlexAdjustment {
+    pub date: NaiveDate,
+    // Negative for hours taken out of the balance
+    pub minutes: i64,
+    #[serde(default)]
+    pub comment: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum AbsenceKind {
+    Vacation,
+    SickLeave,
+    // Staying home with a sick child
+    ChildSick,
+    ParentalLeave,
+    UnpaidLeave,
+}
+impl AbsenceKind {
+    pub const ALL: [AbsenceKind; 5] = [
+        AbsenceKind::Vacation,
+        AbsenceKind::SickLeave,
+        AbsenceKind::ChildSick,
+        AbsenceKind::ParentalLeave,
+        AbsenceKind::UnpaidLeave,
+    ];
+    // Name used on the command line and in the database
+    pub fn name(&self) -> &'static str {
+        match self {
+            AbsenceKind::Vacation => "vacation",
+            AbsenceKind::SickLeave => "sick",
+            AbsenceKind::ChildSick => "child-sick",
+            AbsenceKind::ParentalLeave => "parental",
+            AbsenceKind::UnpaidLeave => "unpaid",
+        }
+    }
+    // Accepts the name or its first letter
+    pub fn parse(s: &str) -> Option<AbsenceKind> {
+        let s = s.to_lowercase();
+        AbsenceKind::ALL
+            .iter()
+            .find(|k| k.name() == s || (s.len() == 1 && k.name().starts_with(&s)))
+            .copied()
+    }
+}
+
+/* Time off on a day, kept apart from the timecodes. It counts toward the
+ * daily target like logged hours do.
+ */
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Absence {
+    pub kind: AbsenceKind,
+    pub minutes: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct TimekeeperData {
+    pub years: HashMap<usize, Year>,
+    // Stopwatch started on a cell, kept here so it survives restarts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer: Option<RunningTimer>,
+    // Manual changes to the flex balance, in the order they were added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flex_adjustments: Vec<FlexAdjustment>,
+    // Days or parts of days off, by date
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub absences: BTreeMap<NaiveDate, Absence>,
+}
 
 impl TimekeeperData {
     pub fn get(&self, year: usize) -> Option<&Year> {
-        Some(self.0.get(&year)?)
+        self.years.get(&year)
     }
 
     pub fn get_mut(&mut self, year: usize) -> Option<&mut Year> {
-        Some(self.0.get_mut(&year)?)
+        self.years.get_mut(&year)
     }
 
     // Gets timecodes of currently active week.
@@ -19,20 +116,156 @@ impl TimekeeperData {
         week.0.iter().map(|t| t.timecode.clone()).collect()
     }
 
-    pub fn add_timecode(&mut self, week: u8, year: usize, timecode: Timecode) {
-        self.get_mut(year)
-            .unwrap()
-            .get_mut(week)
-            .unwrap()
+    // Sets a day on the named timecode, adding the timecode to the week if needed
+    pub fn set_day(&mut self, year: usize, week: u8, timecode: &str, day: u8, value: Day) {
+        let w = self.week_entry(year, week);
+        w.add_timecodes(vec![Timecode::from_string(timecode.to_string())]);
+        if let Some(tc) = w.0.iter_mut().find(|tc| tc.timecode == timecode) {
+            tc.set_day(day, value);
+        }
+    }
+
+    /* The days the running timer's time goes to when stopped at `now`, with the
+     * cell before and after. Time past midnight goes to the days after the one
+     * it was started on, which may be in later weeks.
+     */
+    pub fn clocked_days(
+        &self,
+        timer: &RunningTimer,
+        now: NaiveDateTime,
+    ) -> Vec<(NaiveDate, Option<Day>, Day)> {
+        let first = match date_of(timer.year, timer.week, timer.day) {
+            Some(d) => d,
+            None => return vec![],
+        };
+        let mut days = vec![];
+        for (offset, (start, end, minutes)) in timer.day_parts(now).into_iter().enumerate() {
+            if minutes == 0 {
+                continue;
+            }
+            let date = first + Duration::days(offset as i64);
+            let before = self
+                .get(date.iso_week().year() as usize)
+                .and_then(|y| y.get(date.iso_week().week() as u8))
+                .and_then(|w| w.0.iter().find(|tc| tc.timecode == timer.timecode))
+                .and_then(|tc| tc.get(date.weekday().num_days_from_monday() as u8))
+                .cloned();
+            let mut after = before.clone().unwrap_or_else(|| Day::new(0));
+            after.add_clocked(start, end, minutes);
+            days.push((date, before, after));
+        }
+        days
+    }
+
+    pub fn clear_day(&mut self, year: usize, week: u8, timecode: &str, day: u8) {
+        if let Some(w) = self.get_mut(year).and_then(|y| y.get_mut(week)) {
+            if let Some(tc) = w.0.iter_mut().find(|tc| tc.timecode == timecode) {
+                tc.clear_day(day);
+            }
+        }
+    }
+
+    // Weeks where the timecode is listed
+    pub fn weeks_with(&self, timecode: &str) -> Vec<(usize, u8)> {
+        self.week_keys()
+            .into_iter()
+            .filter(|(y, w)| {
+                self.get(*y)
+                    .and_then(|year| year.get(*w))
+                    .map(|week| week.0.iter().any(|tc| tc.timecode == timecode))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    /* Renames a timecode in every week, merging its days into `to` where that
+     * is already listed. Returns the weeks that changed.
+     */
+    pub fn rename_timecode(&mut self, from: &str, to: &str) -> Vec<(usize, u8)> {
+        let weeks = self.weeks_with(from);
+        for (year, week) in weeks.iter() {
+            self.week_entry(*year, *week).rename_timecode(from, to);
+        }
+        if let Some(timer) = self.timer.as_mut().filter(|t| t.timecode == from) {
+            timer.timecode = to.to_string();
+        }
+        weeks
+    }
+
+    // Gets a week, creating it if it doesn't exist
+    pub fn week_entry(&mut self, year: usize, week: u8) -> &mut Week {
+        self.years
+            .entry(year)
+            .or_insert_with(|| Year(HashMap::new()))
             .0
-            .push(timecode);
+            .entry(week)
+            .or_insert_with(|| Week(vec![]))
+    }
+
+    // (year, week) of every stored week, oldest first
+    pub fn week_keys(&self) -> Vec<(usize, u8)> {
+        let mut keys: Vec<(usize, u8)> = self
+            .years
+            .iter()
+            .flat_map(|(y, year)| year.0.keys().map(move |w| (*y, *w)))
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    // All days logged between `from` and `to`, inclusive, ordered by date
+    pub fn days_between(&self, from: NaiveDate, to: NaiveDate) -> Vec<DayRecord> {
+        let mut records = vec![];
+        for (year, week) in self.week_keys().into_iter() {
+            let w = self.get(year).unwrap().get(week).unwrap();
+            for day in 0..7 {
+                let date = match date_of(year, week, day) {
+                    Some(d) if d >= from && d <= to => d,
+                    _ => continue,
+                };
+                for tc in w.0.iter() {
+                    if let Some(d) = tc.get(day) {
+                        records.push(DayRecord {
+                            date,
+                            timecode: tc.timecode.clone(),
+                            minutes: d.minutes,
+                            comment: d.comment.clone(),
+                            entries: d.entries.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        // Stable, so timecodes keep their order within the week
+        records.sort_by_key(|r| r.date);
+        records
+    }
+
+    pub fn absence_minutes(&self, date: NaiveDate) -> u32 {
+        self.absences.get(&date).map(|a| a.minutes).unwrap_or(0)
+    }
+
+    // Minutes absent per kind between `from` and `to`, inclusive
+    pub fn absence_totals(&self, from: NaiveDate, to: NaiveDate) -> Vec<(AbsenceKind, u32)> {
+        AbsenceKind::ALL
+            .iter()
+            .map(|kind| {
+                let minutes = self
+                    .absences
+                    .range(from..=to)
+                    .filter(|(_, a)| a.kind == *kind)
+                    .map(|(_, a)| a.minutes)
+                    .sum();
+                (*kind, minutes)
+            })
+            .collect()
     }
 
     // Adds starred timecodes to current week, or creates new week if no exists
     // TODO: Timecode with all days set to null should not load/be shown
     pub fn load_week(&mut self, week: u8, year: usize, starred_timecodes: Vec<Timecode>) {
         let year_data = self
-            .0
+            .years
             .entry(year)
             .or_insert(Year(HashMap::<u8, Week>::new()));
         match year_data.0.get_mut(&week) {
@@ -53,14 +286,14 @@ impl TimekeeperData {
 pub struct Year(pub HashMap<u8, Week>);
 impl Year {
     pub fn get(&self, week: u8) -> Option<&Week> {
-        Some(self.0.get(&week)?)
+        self.0.get(&week)
     }
     pub fn get_mut(&mut self, week: u8) -> Option<&mut Week> {
-        Some(self.0.get_mut(&week)?)
+        self.0.get_mut(&week)
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Week(pub Vec<Timecode>);
 impl Week {
     pub fn get(&self, timecode: usize) -> Option<&Timecode> {
@@ -77,22 +310,65 @@ impl Week {
             None
         }
     }
-    // TODO: Figure out how to do timecode ordering
+    // Adds the timecodes that aren't listed yet after the existing ones, keeping their order
     pub fn add_timecodes(&mut self, timecodes: Vec<Timecode>) {
         for new_tc in timecodes.into_iter() {
-            if let None = self.0.iter().find(|tc| tc.timecode == new_tc.timecode) {
+            if self
+                .0
+                .iter()
+                .find(|tc| tc.timecode == new_tc.timecode)
+                .is_none()
+            {
                 // self.0.insert(0, new_tc);
                 self.0.push(new_tc);
             }
         }
     }
+    pub fn rename_timecode(&mut self, from: &str, to: &str) {
+        let idx = match self.0.iter().position(|tc| tc.timecode == from) {
+            Some(i) => i,
+            None => return,
+        };
+        match self.0.iter().position(|tc| tc.timecode == to) {
+            Some(_) => {
+                let source = self.0.remove(idx);
+                if let Some(target) = self.0.iter_mut().find(|tc| tc.timecode == to) {
+                    target.merge(source);
+                }
+            }
+            None => self.0[idx].timecode = to.to_string(),
+        }
+    }
+    // Swaps a timecode with its neighbour above (-1) or below (1). Returns the new index
+    pub fn move_timecode(&mut self, idx: usize, offset: i32) -> Option<usize> {
+        let target = idx as i32 + offset;
+        if idx >= self.0.len() || target < 0 || target as usize >= self.0.len() {
+            return None;
+        }
+        self.0.swap(idx, target as usize);
+        Some(target as usize)
+    }
+    pub fn remove_timecode(&mut self, timecode: &str) {
+        self.0.retain(|tc| tc.timecode != timecode);
+    }
     pub fn remove_empty(&mut self) {
         self.0.retain(|tc| !tc.is_empty());
     }
+    // Minutes logged on a day across all timecodes
+    pub fn day_minutes(&self, day: u8) -> u32 {
+        self.0
+            .iter()
+            .filter_map(|tc| tc.get(day))
+            .map(|d| d.minutes)
+            .sum()
+    }
+    pub fn total_minutes(&self) -> u32 {
+        self.0.iter().map(|tc| tc.total_minutes()).sum()
+    }
 }
 
 // TODO: Timecodes with no content (i.e. all days are None) should not serialize
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Timecode {
     pub timecode: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -149,19 +425,40 @@ impl Timecode {
             _ => panic!("ERR: Invalid date passed to set_day!"),
         }
     }
+    pub fn clear_day(&mut self, day_idx: u8) {
+        match day_idx {
+            0 => self.monday = None,
+            1 => self.tuesday = None,
+            2 => self.wednesday = None,
+            3 => self.thursday = None,
+            4 => self.friday = None,
+            5 => self.saturday = None,
+            6 => self.sunday = None,
+            _ => (),
+        }
+    }
+    pub fn total_minutes(&self) -> u32 {
+        (0..7).filter_map(|d| self.get(d)).map(|d| d.minutes).sum()
+    }
+    // Adds the days of `other`, combining days logged on both
+    pub fn merge(&mut self, other: Timecode) {
+        for day_idx in 0..7 {
+            if let Some(day) = other.get(day_idx).cloned() {
+                match self.get_mut(day_idx) {
+                    Some(existing) => existing.merge(day),
+                    None => self.set_day(day_idx, day),
+                }
+            }
+        }
+    }
     pub fn is_empty(&self) -> bool {
-        if self.monday.is_some()
+        !(self.monday.is_some()
             || self.tuesday.is_some()
             || self.wednesday.is_some()
             || self.thursday.is_some()
             || self.friday.is_some()
             || self.saturday.is_some()
-            || self.sunday.is_some()
-        {
-            false
-        } else {
-            true
-        }
+            || self.sunday.is_some())
     }
     // impl From seems too implicit for this
     pub fn from_string(tc_string: String) -> Timecode {
@@ -178,8 +475,331 @@ impl Timecode {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+// A stopwatch running on a timecode/day cell
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct RunningTimer {
+    pub year: usize,
+    pub week: u8,
+    pub timecode: String,
+    pub day: u8,
+    pub started: DateTime<Local>,
+}
+
+impl RunningTimer {
+    pub fn elapsed(&self) -> Duration {
+        Local::now() - self.started
+    }
+    /* The whole minutes from the start until `now`, split at midnight into the
+     * start, end and length of each day's part, from the day it was started on.
+     * A part lasting until midnight ends at 00:00.
+     */
+    pub fn day_parts(&self, now: NaiveDateTime) -> Vec<(NaiveTime, NaiveTime, u32)> {
+        let whole_minute = |t: NaiveDateTime| t.date().and_hms(t.hour(), t.minute(), 0);
+        let start = whole_minute(self.started.naive_local());
+        let end = whole_minute(now).max(start);
+        let mut parts = vec![];
+        let mut from = start;
+        loop {
+            let to = end.min(from.date().succ().and_hms(0, 0, 0));
+            parts.push((from.time(), to.time(), (to - from).num_minutes() as u32));
+            if to == end {
+                return parts;
+            }
+            from = to;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Day {
-    pub hours: f32,
+    // Stored as whole minutes to avoid float drift when summing
+    pub minutes: u32,
     pub comment: String,
+    // When the work happened. If there are any, `minutes` is their total
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<Entry>,
+}
+
+impl Day {
+    pub fn new(minutes: u32) -> Day {
+        Day {
+            minutes,
+            comment: String::from(""),
+            entries: vec![],
+        }
+    }
+    /* Sums the hours, joins the comments and combines the entries of two days.
+     * Hours logged without entries next to a day with them become an entry after
+     * its last one, since the total is taken from the entries from then on.
+     */
+    pub fn merge(&mut self, mut other: Day) {
+        if self.entries.is_empty() && self.minutes > 0 && !other.entries.is_empty() {
+            self.entries
+                .push(Entry::after(&other.entries, self.minutes));
+        } else if other.entries.is_empty() && other.minutes > 0 && !self.entries.is_empty() {
+            other
+                .entries
+                .push(Entry::after(&self.entries, other.minutes));
+        }
+        self.minutes += other.minutes;
+        if self.comment.is_empty() {
+            self.comment = other.comment;
+        } else if !other.comment.is_empty() {
+            self.comment = format!("{}; {}", self.comment, other.comment);
+        }
+        self.entries.extend(other.entries);
+        self.entries.sort_by_key(|e| e.start);
+        self.sync_minutes();
+    }
+    /* Adds time clocked from `start` to `end`: as another entry on a day kept as
+     * entries, or to the minutes otherwise. An entry can't last a whole day, so
+     * one for a full day ends a minute short.
+     */
+    pub fn add_clocked(&mut self, start: NaiveTime, end: NaiveTime, minutes: u32) {
+        if self.entries.is_empty() {
+            self.minutes += minutes;
+            return;
+        }
+        let end = if minutes >= 24 * 60 {
+            NaiveTime::from_hms(23, 59, 0)
+        } else {
+            end
+        };
+        self.entries.push(Entry {
+            start,
+            end,
+            note: String::new(),
+        });
+        self.entries.sort_by_key(|e| e.start);
+        self.sync_minutes();
+    }
+    // Keeps the day's total in line with its entries after they change
+    pub fn sync_minutes(&mut self) {
+        if !self.entries.is_empty() {
+            self.minutes = self.entries.iter().map(|e| e.minutes()).sum();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Entry {
+    #[serde(with = "hhmm")]
+    pub start: NaiveTime,
+    #[serde(with = "hhmm")]
+    pub end: NaiveTime,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub note: String,
+}
+
+impl Entry {
+    // An end before the start means the entry runs past midnight
+    pub fn minutes(&self) -> u32 {
+        let mins = (self.end - self.start).num_minutes();
+        if mins < 0 {
+            (mins + 24 * 60) as u32
+        } else {
+            mins as u32
+        }
+    }
+
+    // `minutes` long, starting where the last of `entries` ends
+    pub fn after(entries: &[Entry], minutes: u32) -> Entry {
+        let start = entries
+            .iter()
+            .max_by_key(|e| e.start)
+            .map(|e| e.end)
+            .unwrap_or_else(|| NaiveTime::from_hms(0, 0, 0));
+        Entry {
+            start,
+            end: start + Duration::minutes(minutes as i64),
+            note: String::new(),
+        }
+    }
+
+    // Parses "09:00-10:30 optional note", also with spaces around the dash
+    pub fn parse(input: &str) -> Option<Entry> {
+        let (start, rest) = input.split_once('-')?;
+        let rest = rest.trim_start();
+        let (end, note) = match rest.split_once(char::is_whitespace) {
+            Some((e, n)) => (e, n.trim()),
+            None => (rest, ""),
+        };
+        Some(Entry {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?,
+            note: note.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{} {}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            self.note
+        )
+    }
+}
+
+pub mod hhmm {
+    use chrono::NaiveTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&time.format("%H:%M").to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<NaiveTime, D::Error> {
+        let s = String::deserialize(d)?;
+        NaiveTime::parse_from_str(&s, "%H:%M").map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms(h, m, 0)
+    }
+
+    #[test]
+    fn parses_entries() {
+        let cases = [
+            ("09:00-10:30", (9, 0), (10, 30), ""),
+            ("09:00 - 10:30", (9, 0), (10, 30), ""),
+            (
+                " 9:00 -10:30  code review ",
+                (9, 0),
+                (10, 30),
+                "code review",
+            ),
+            ("22:00- 01:00 re-deploy", (22, 0), (1, 0), "re-deploy"),
+        ];
+        for (input, start, end, note) in cases {
+            let entry = Entry::parse(input).unwrap();
+            assert_eq!(entry.start, time(start.0, start.1), "{}", input);
+            assert_eq!(entry.end, time(end.0, end.1), "{}", input);
+            assert_eq!(entry.note, note, "{}", input);
+        }
+        assert!(Entry::parse("09:00").is_none());
+        assert!(Entry::parse("9-10").is_none());
+        assert!(Entry::parse("09:00 10:30").is_none());
+    }
+
+    fn timed(entries: &[&str]) -> Day {
+        let mut day = Day::new(0);
+        day.entries = entries.iter().map(|e| Entry::parse(e).unwrap()).collect();
+        day.sync_minutes();
+        day
+    }
+
+    fn ranges(day: &Day) -> Vec<(NaiveTime, NaiveTime)> {
+        day.entries.iter().map(|e| (e.start, e.end)).collect()
+    }
+
+    #[test]
+    fn merging_days_with_and_without_entries_keeps_all_hours() {
+        // Entries on the existing day, bare hours merged in
+        let mut day = timed(&["09:00-10:00", "13:00-14:30"]);
+        day.merge(Day::new(90));
+        assert_eq!(day.minutes, 240);
+        assert_eq!(
+            ranges(&day),
+            [
+                (time(9, 0), time(10, 0)),
+                (time(13, 0), time(14, 30)),
+                (time(14, 30), time(16, 0))
+            ]
+        );
+        day.sync_minutes();
+        assert_eq!(day.minutes, 240);
+
+        // Bare hours on the existing day, entries merged in
+        let mut day = Day::new(30);
+        day.merge(timed(&["08:00-09:00"]));
+        assert_eq!(day.minutes, 90);
+        assert_eq!(
+            ranges(&day),
+            [(time(8, 0), time(9, 0)), (time(9, 0), time(9, 30))]
+        );
+
+        // A day with only a comment adds no entry
+        let mut comment = Day::new(0);
+        comment.comment = String::from("Out of office");
+        let mut day = timed(&["08:00-09:00"]);
+        day.merge(comment);
+        assert_eq!(day.minutes, 60);
+        assert_eq!(day.entries.len(), 1);
+        assert_eq!(day.comment, "Out of office");
+
+        // Without entries on either side the hours are summed
+        let mut day = Day::new(30);
+        day.merge(Day::new(45));
+        assert_eq!(day.minutes, 75);
+        assert!(day.entries.is_empty());
+    }
+
+    fn timer(started: (u32, u32, u32), day: u8) -> RunningTimer {
+        use chrono::TimeZone;
+        RunningTimer {
+            year: 2026,
+            week: 42,
+            timecode: String::from("ACME"),
+            day,
+            started: Local
+                .ymd(2026, 10, started.0)
+                .and_hms(started.1, started.2, 0),
+        }
+    }
+
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2026, 10, day).and_hms(h, m, 0)
+    }
+
+    #[test]
+    fn timer_running_overnight_is_split_at_midnight() {
+        let mut data = TimekeeperData::default();
+        data.set_day(2026, 42, "ACME", 2, Day::new(60));
+        data.set_day(2026, 42, "ACME", 3, timed(&["09:00-10:00"]));
+
+        // Wednesday 22:30 to Thursday 01:15
+        let days = data.clocked_days(&timer((14, 22, 30), 2), at(15, 1, 15));
+        assert_eq!(days.len(), 2);
+        let (date, before, wednesday) = &days[0];
+        assert_eq!(*date, NaiveDate::from_ymd(2026, 10, 14));
+        assert_eq!(before.as_ref().map(|d| d.minutes), Some(60));
+        assert_eq!(wednesday.minutes, 150);
+        let (date, _, thursday) = &days[1];
+        assert_eq!(*date, NaiveDate::from_ymd(2026, 10, 15));
+        assert_eq!(
+            ranges(thursday),
+            [(time(0, 0), time(1, 15)), (time(9, 0), time(10, 0))]
+        );
+        assert_eq!(thursday.minutes, 135);
+    }
+
+    #[test]
+    fn timer_running_over_a_weekend_logs_every_day() {
+        let data = TimekeeperData::default();
+        // Friday 16:00 to Monday 09:00, in the next week
+        let days = data.clocked_days(&timer((16, 16, 0), 4), at(19, 9, 0));
+        let logged: Vec<(u32, u32)> = days
+            .iter()
+            .map(|(d, _, day)| (d.day(), day.minutes))
+            .collect();
+        assert_eq!(logged, [(16, 480), (17, 1440), (18, 1440), (19, 540)]);
+        assert_eq!(days[3].0.iso_week().week(), 43);
+
+        // A whole day kept as entries is as long as an entry can be
+        let mut day = timed(&["09:00-10:00"]);
+        day.add_clocked(time(0, 0), time(0, 0), 1440);
+        assert_eq!(
+            ranges(&day),
+            [(time(0, 0), time(23, 59)), (time(9, 0), time(10, 0))]
+        );
+    }
 }
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum DurationFormat {
+    // 7.5
+    Decimal,
+    // 7:30
+    HoursMinutes,
+}
+
+pub fn format_minutes(minutes: u32, format: DurationFormat) -> String {
+    match format {
+        DurationFormat::Decimal => {
+            let hours = format!("{:.2}", minutes as f64 / 60.0);
+            hours
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        }
+        DurationFormat::HoursMinutes => format!("{}:{:02}", minutes / 60, minutes % 60),
+    }
+}
+
+/* Parses either decimal hours ("1.5", "1,5", "2") or hours and minutes ("1:30"),
+ * regardless of the configured display format. None if it doesn't fit in a u32.
+ */
+pub fn parse_duration(input: &str) -> Option<u32> {
+    let input = input.trim();
+    if let Some((h, m)) = input.split_once(':') {
+        let h: u32 = if h.is_empty() { 0 } else { h.parse().ok()? };
+        let m: u32 = m.parse().ok()?;
+        if m >= 60 {
+            return None;
+        }
+        return h.checked_mul(60)?.checked_add(m);
+    }
+    let hours: f64 = input.replace(',', ".").parse().ok()?;
+    let minutes = (hours * 60.0).round();
+    if !minutes.is_finite() || minutes < 0.0 || minutes > u32::MAX as f64 {
+        return None;
+    }
+    Some(minutes as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_decimal_hours_and_hours_minutes() {
+        let cases = [
+            ("1.5", Some(90)),
+            ("1,5", Some(90)),
+            ("2", Some(120)),
+            (" 0.25 ", Some(15)),
+            ("1:30", Some(90)),
+            (":45", Some(45)),
+            ("0:05", Some(5)),
+            ("1:60", None),
+            ("-1", None),
+            ("abc", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn overflow_is_rejected() {
+        assert_eq!(parse_duration("99999999:00"), None);
+        assert_eq!(parse_duration("99999999999"), None);
+        assert_eq!(parse_duration("71582788:15"), Some(u32::MAX));
+    }
+
+    #[test]
+    fn formats_minutes() {
+        let cases = [
+            (0, "0", "0:00"),
+            (15, "0.25", "0:15"),
+            (90, "1.5", "1:30"),
+            (100, "1.67", "1:40"),
+            (480, "8", "8:00"),
+        ];
+        for (minutes, decimal, hours_minutes) in cases {
+            assert_eq!(format_minutes(minutes, DurationFormat::Decimal), decimal);
+            assert_eq!(
+                format_minutes(minutes, DurationFormat::HoursMinutes),
+                hours_minutes
+            );
+        }
+    }
+}
@@ -0,0 +1,488 @@
+use crate::clock::{self, ClockFormat};
+use crate::config::Config;
+use crate::data::{date_of, DayRecord, TimekeeperData};
+use crate::i18n::I18n;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// A column of the long format, one row per timecode and day
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ExportColumn {
+    Date,
+    // ISO week number
+    Week,
+    Weekday,
+    Timecode,
+    Description,
+    Client,
+    Project,
+    // Decimal hours
+    Hours,
+    Comment,
+}
+
+impl ExportColumn {
+    fn header(&self) -> &'static str {
+        match self {
+            ExportColumn::Date => "date",
+            ExportColumn::Week => "week",
+            ExportColumn::Weekday => "weekday",
+            ExportColumn::Timecode => "timecode",
+            ExportColumn::Description => "description",
+            ExportColumn::Client => "client",
+            ExportColumn::Project => "project",
+            ExportColumn::Hours => "hours",
+            ExportColumn::Comment => "comment",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExportLayout {
+    // One row per timecode and day, with the configured columns
+    Long,
+    // One grid per week like the timesheet, timecodes down and days across
+    Wide,
+}
+
+enum Value {
+    Text(String),
+    Hours(u32),
+    Empty,
+}
+
+// A worksheet in .xlsx; in CSV the sheets follow each other
+struct Sheet {
+    name: String,
+    rows: Vec<Vec<Value>>,
+}
+
+// A block of logged time in an iCalendar export
+struct CalendarEvent {
+    date: NaiveDate,
+    timecode: String,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    description: String,
+}
+
+/* Writes the hours between `from` and `to` to `path`, as CSV, Excel, iCalendar,
+ * timeclock or Org by the extension. Returns the number of timecode rows, or
+ * calendar events, written.
+ */
+pub fn export(
+    data: &TimekeeperData,
+    conf: &Config,
+    from: NaiveDate,
+    to: NaiveDate,
+    layout: ExportLayout,
+    path: &Path,
+) -> Result<usize, Box<dyn Error>> {
+    if is_calendar(path) {
+        let events = calendar_events(data, conf, from, to);
+        write_ics(&events, conf, path)?;
+        return Ok(events.len());
+    }
+    if let Some(format) = ClockFormat::from_path(path) {
+        let records: Vec<DayRecord> = data
+            .days_between(from, to)
+            .into_iter()
+            .filter(|r| r.minutes > 0 || !r.comment.is_empty())
+            .collect();
+        fs::write(path, clock::write(&records, format, conf))?;
+        return Ok(records.len());
+    }
+    // Checked up front, since chrono panics when formatting with an invalid format
+    if StrftimeItems::new(&conf.export_date_format).any(|i| i == Item::Error) {
+        return Err(format!("Invalid export_date_format {}", conf.export_date_format).into());
+    }
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    let sheets = match layout {
+        ExportLayout::Long => long_sheets(data, conf, from, to),
+        ExportLayout::Wide => wide_sheets(data, conf, from, to),
+    };
+    let count = match layout {
+        ExportLayout::Long => sheets.iter().map(|s| s.rows.len() - 1).sum(),
+        // Less the header and total rows of every week
+        ExportLayout::Wide => sheets.iter().map(|s| s.rows.len() - 2).sum(),
+    };
+    match extension.as_deref() {
+        Some("csv") => write_csv(&sheets, conf, path)?,
+        Some("xlsx") => write_xlsx(&sheets, path)?,
+        _ => {
+            return Err(format!(
+                "Unknown export format {}, use .csv, .xlsx, .ics, .timeclock or .org",
+                path.display()
+            )
+            .into())
+        }
+    }
+    Ok(count)
+}
+
+// Whether `path` is exported as calendar events rather than rows
+pub fn is_calendar(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("ics"))
+        .unwrap_or(false)
+}
+
+fn long_sheets(data: &TimekeeperData, conf: &Config, from: NaiveDate, to: NaiveDate) -> Vec<Sheet> {
+    let columns = &conf.export_columns;
+    let mut rows = vec![columns
+        .iter()
+        .map(|c| Value::Text(c.header().to_string()))
+        .collect()];
+    for record in data.days_between(from, to) {
+        if record.minutes == 0 && record.comment.is_empty() {
+            continue;
+        }
+        let info = conf.timecode_info(&record.timecode);
+        let text = |s: &str| Value::Text(s.to_string());
+        rows.push(
+            columns
+                .iter()
+                .map(|c| match c {
+                    ExportColumn::Date => {
+                        text(&record.date.format(&conf.export_date_format).to_string())
+                    }
+                    ExportColumn::Week => text(&record.date.iso_week().week().to_string()),
+                    ExportColumn::Weekday => {
+                        let day = record.date.weekday().num_days_from_monday() as usize;
+                        text(I18n::day_labels(&conf.lang)[day])
+                    }
+                    ExportColumn::Timecode => text(&record.timecode),
+                    ExportColumn::Description => {
+                        text(info.map(|i| i.description.as_str()).unwrap_or(""))
+                    }
+                    ExportColumn::Client => text(info.map(|i| i.client.as_str()).unwrap_or("")),
+                    ExportColumn::Project => text(info.map(|i| i.project.as_str()).unwrap_or("")),
+                    ExportColumn::Hours => Value::Hours(record.minutes),
+                    ExportColumn::Comment => text(&record.comment),
+                })
+                .collect(),
+        );
+    }
+    vec![Sheet {
+        name: format!("{} - {}", from, to),
+        rows,
+    }]
+}
+
+fn wide_sheets(data: &TimekeeperData, conf: &Config, from: NaiveDate, to: NaiveDate) -> Vec<Sheet> {
+    let lang = &conf.lang;
+    let day_labels = I18n::day_labels(lang);
+    let mut sheets = vec![];
+    for (year, week) in data.week_keys() {
+        let dates: Vec<Option<NaiveDate>> = (0..7)
+            .map(|day| date_of(year, week, day).filter(|d| *d >= from && *d <= to))
+            .collect();
+        if dates.iter().all(|d| d.is_none()) {
+            continue;
+        }
+        let w = data.get(year).unwrap().get(week).unwrap();
+
+        let mut header = vec![Value::Text(format!(
+            "{} {}, {}",
+            I18n::week_label(lang),
+            week,
+            year
+        ))];
+        for (day, date) in dates.iter().enumerate() {
+            header.push(Value::Text(match date {
+                Some(d) => format!("{} {}", day_labels[day], d.format(&conf.export_date_format)),
+                None => day_labels[day].to_string(),
+            }));
+        }
+        header.push(Value::Text(I18n::total_label(lang).to_string()));
+
+        let mut rows = vec![header];
+        let mut day_totals = [0; 7];
+        for tc in w.0.iter() {
+            let minutes: Vec<Option<u32>> = (0..7)
+                .map(|day| {
+                    dates[day]?;
+                    tc.get(day as u8).map(|d| d.minutes)
+                })
+                .collect();
+            let total: u32 = minutes.iter().flatten().sum();
+            if total == 0 {
+                continue;
+            }
+            let mut row = vec![Value::Text(tc.timecode.clone())];
+            for (day, m) in minutes.iter().enumerate() {
+                day_totals[day] += m.unwrap_or(0);
+                row.push(match m {
+                    Some(m) if *m > 0 => Value::Hours(*m),
+                    _ => Value::Empty,
+                });
+            }
+            row.push(Value::Hours(total));
+            rows.push(row);
+        }
+        if rows.len() == 1 {
+            continue;
+        }
+        let mut totals = vec![Value::Text(I18n::total_label(lang).to_string())];
+        totals.extend(dates.iter().zip(day_totals.iter()).map(|(d, m)| match d {
+            Some(_) => Value::Hours(*m),
+            None => Value::Empty,
+        }));
+        totals.push(Value::Hours(day_totals.iter().sum()));
+        rows.push(totals);
+        sheets.push(Sheet {
+            name: format!("{}-W{:02}", year, week),
+            rows,
+        });
+    }
+    sheets
+}
+
+fn hours(minutes: u32) -> f64 {
+    (minutes as f64 / 60.0 * 100.0).round() / 100.0
+}
+
+fn write_csv(sheets: &[Sheet], conf: &Config, path: &Path) -> Result<(), Box<dyn Error>> {
+    let delimiter = if conf.export_delimiter.is_ascii() {
+        conf.export_delimiter as u8
+    } else {
+        return Err(format!("Invalid export_delimiter {}", conf.export_delimiter).into());
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_path(path)?;
+    for row in sheets.iter().flat_map(|s| s.rows.iter()) {
+        writer.write_record(row.iter().map(|v| match v {
+            Value::Text(s) => s.clone(),
+            Value::Hours(m) => hours(*m).to_string(),
+            Value::Empty => String::new(),
+        }))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_xlsx(sheets: &[Sheet], path: &Path) -> Result<(), Box<dyn Error>> {
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+    let hours_format = Format::new().set_num_format("0.00");
+    for sheet in sheets {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet.name)?;
+        for (r, row) in sheet.rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                let (r, c) = (r as u32, c as u16);
+                match value {
+                    Value::Text(s) if r == 0 => {
+                        worksheet.write_string_with_format(r, c, s, &bold)?;
+                    }
+                    Value::Text(s) => {
+                        worksheet.write_string(r, c, s)?;
+                    }
+                    Value::Hours(m) => {
+                        worksheet.write_number_with_format(r, c, hours(*m), &hours_format)?;
+                    }
+                    Value::Empty => (),
+                }
+            }
+        }
+        worksheet.autofit();
+    }
+    workbook.save(path)?;
+    Ok(())
+}
+
+/* One event per timecode and day. Days with time entries run from the first
+ * entry to the end of the last. Other hours are laid out one after another from
+ * `export_day_start`, moved past any block at the same time.
+ */
+fn calendar_events(
+    data: &TimekeeperData,
+    conf: &Config,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<CalendarEvent> {
+    let records: Vec<DayRecord> = data
+        .days_between(from, to)
+        .into_iter()
+        .filter(|r| r.minutes > 0)
+        .collect();
+    let mut dates: Vec<NaiveDate> = records.iter().map(|r| r.date).collect();
+    dates.dedup();
+
+    let mut events = vec![];
+    for date in dates {
+        let (timed, untimed): (Vec<&DayRecord>, Vec<&DayRecord>) = records
+            .iter()
+            .filter(|r| r.date == date)
+            .partition(|r| !r.entries.is_empty());
+
+        let mut blocks: Vec<CalendarEvent> = timed
+            .into_iter()
+            .map(|r| {
+                let start = r
+                    .entries
+                    .iter()
+                    .map(|e| date.and_time(e.start))
+                    .min()
+                    .unwrap();
+                let end = r
+                    .entries
+                    .iter()
+                    .map(|e| date.and_time(e.start) + Duration::minutes(e.minutes() as i64))
+                    .max()
+                    .unwrap();
+                calendar_event(conf, r, start, end)
+            })
+            .collect();
+
+        let taken: Vec<(NaiveDateTime, NaiveDateTime)> =
+            blocks.iter().map(|b| (b.start, b.end)).collect();
+        let minutes: Vec<u32> = untimed.iter().map(|r| r.minutes).collect();
+        let slots = clock::lay_out_untimed(date.and_time(conf.export_day_start), &taken, &minutes);
+        for (r, (start, end)) in untimed.into_iter().zip(slots) {
+            blocks.push(calendar_event(conf, r, start, end));
+        }
+        blocks.sort_by_key(|b| b.start);
+        events.extend(blocks);
+    }
+    events
+}
+
+fn calendar_event(
+    conf: &Config,
+    record: &DayRecord,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> CalendarEvent {
+    let mut lines = vec![];
+    if let Some(info) = conf.timecode_info(&record.timecode) {
+        if !info.description.is_empty() {
+            lines.push(info.description.clone());
+        }
+    }
+    if !record.comment.is_empty() {
+        lines.push(record.comment.clone());
+    }
+    for e in record.entries.iter() {
+        let mut line = format!("{}-{}", e.start.format("%H:%M"), e.end.format("%H:%M"));
+        if !e.note.is_empty() {
+            line.push(' ');
+            line.push_str(&e.note);
+        }
+        lines.push(line);
+    }
+    CalendarEvent {
+        date: record.date,
+        timecode: record.timecode.clone(),
+        start,
+        end,
+        description: lines.join("\n"),
+    }
+}
+
+// Times are floating, so calendar apps show them in their own time zone as logged
+fn write_ics(events: &[CalendarEvent], conf: &Config, path: &Path) -> Result<(), Box<dyn Error>> {
+    const TIME: &str = "%Y%m%dT%H%M%S";
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//timekeeper//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+    for event in events {
+        lines.push(String::from("BEGIN:VEVENT"));
+        // Stable, so exporting a range again updates the events instead of adding more
+        lines.push(format!(
+            "UID:{}-{}@timekeeper",
+            event.date.format("%Y%m%d"),
+            escape(&event.timecode)
+        ));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("DTSTART:{}", event.start.format(TIME)));
+        lines.push(format!("DTEND:{}", event.end.format(TIME)));
+        lines.push(format!("SUMMARY:{}", escape(&event.timecode)));
+        if let Some(info) = conf.timecode_info(&event.timecode) {
+            if !info.client.is_empty() {
+                lines.push(format!("CATEGORIES:{}", escape(&info.client)));
+            }
+        }
+        if !event.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&event.description)));
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    let mut text = String::new();
+    for line in lines {
+        text.push_str(&fold(&line));
+        text.push_str("\r\n");
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes continue on the next, which starts with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Day;
+
+    #[test]
+    fn wide_export_lists_weeks_in_order() {
+        let mut data = TimekeeperData::default();
+        let weeks = [
+            (2026, 42),
+            (2025, 52),
+            (2026, 1),
+            (2026, 9),
+            (2025, 3),
+            (2026, 10),
+        ];
+        for (year, week) in weeks.iter() {
+            data.set_day(*year, *week, "ACME", 0, Day::new(60));
+        }
+        let from = NaiveDate::from_ymd(2025, 1, 1);
+        let to = NaiveDate::from_ymd(2026, 12, 31);
+        let names: Vec<String> = wide_sheets(&data, &Config::default(), from, to)
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["2025-W03", "2025-W52", "2026-W01", "2026-W09", "2026-W10", "2026-W42"]
+        );
+    }
+}
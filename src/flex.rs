@@ -0,0 +1,90 @@
+use crate::config::Config;
+use crate::data::{date_of, TimekeeperData};
+use crate::duration::format_minutes;
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+
+// Hours logged against hours expected over a period, in minutes
+#[derive(Serialize)]
+pub struct FlexBalance {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub logged: i64,
+    // Absences count as hours worked
+    pub absent: i64,
+    pub expected: i64,
+    pub adjusted: i64,
+}
+
+impl FlexBalance {
+    pub fn minutes(&self) -> i64 {
+        self.logged + self.absent - self.expected + self.adjusted
+    }
+}
+
+/* The flex balance at the end of `to`, counted from the configured start date or
+ * else the first day with hours logged or an absence. Every day in between is
+ * expected to have the daily target, including `to` itself. Adjustments up to `to`
+ * are included even if they predate the start, so an opening balance can be
+ * entered as one.
+ * None when there is nothing to count from.
+ */
+pub fn balance(data: &TimekeeperData, conf: &Config, to: NaiveDate) -> Option<FlexBalance> {
+    let first_logged = || {
+        data.week_keys()
+            .into_iter()
+            .flat_map(|(year, week)| {
+                let w = data.get(year).unwrap().get(week).unwrap();
+                (0..7)
+                    .filter(move |day| w.day_minutes(*day) > 0)
+                    .filter_map(move |day| date_of(year, week, day))
+            })
+            .chain(data.absences.keys().copied())
+            .min()
+    };
+    let from = conf.flex_start.or_else(first_logged)?;
+    if from > to {
+        return None;
+    }
+
+    let logged = data
+        .days_between(from, to)
+        .iter()
+        .map(|d| d.minutes as i64)
+        .sum();
+    let absent = data
+        .absences
+        .range(from..=to)
+        .map(|(_, a)| a.minutes as i64)
+        .sum();
+    let mut expected = 0;
+    let mut date = from;
+    while date <= to {
+        expected += conf.daily_target(date) as i64;
+        date += Duration::days(1);
+    }
+    let adjusted = data
+        .flex_adjustments
+        .iter()
+        .filter(|a| a.date <= to)
+        .map(|a| a.minutes)
+        .sum();
+    Some(FlexBalance {
+        from,
+        to,
+        logged,
+        absent,
+        expected,
+        adjusted,
+    })
+}
+
+// "+1:30" or "-0.5", in the configured duration format
+pub fn format_balance(minutes: i64, conf: &Config) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!(
+        "{}{}",
+        sign,
+        format_minutes(minutes.unsigned_abs() as u32, conf.duration_format)
+    )
+}
@@ -39,6 +39,12 @@ pub fn dec_event(key: Key) -> bool {
 pub fn new_timecode_event(key: Key) -> bool {
     matches!(key, Key::Char('N'))
 }
+pub fn move_up_event(key: Key) -> bool {
+    matches!(key, Key::Alt('k'))
+}
+pub fn move_down_event(key: Key) -> bool {
+    matches!(key, Key::Alt('j'))
+}
 pub fn num_event(c: char) -> bool {
     matches!(c, '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9')
 }
@@ -1,13 +1,30 @@
 use crate::app::{App, State};
 use crate::event::Key;
+use crate::export::ExportLayout;
 pub mod common_key_events;
 
 pub fn handle_app(key: Key, app: &mut App) {
+    app.message = None;
     match app.get_state() {
         State::Browsing => handle_browsing(key, app),
         State::WritingComment => handle_comment_input(key, app),
         State::AddingTimecode => handle_timecode_input(key, app),
+        State::EnteringDuration => handle_duration_input(key, app),
+        State::EditingEntries => handle_entries_input(key, app),
+        State::EditingEntry => handle_entry_input(key, app),
         State::ControlScreen => handle_controlscreen_input(key, app),
+        State::RestoringBackup => handle_backup_input(key, app),
+        State::Recovering => handle_recovery_input(key, app),
+        State::Locked => handle_lock_input(key, app),
+        State::ExternalChange => handle_external_change_input(key, app),
+        State::RenamingTimecode => handle_rename_input(key, app),
+        State::ConfirmingDelete => handle_delete_input(key, app),
+        State::EnteringAbsence => handle_absence_input(key, app),
+        State::Reports => handle_reports(key, app),
+        State::EnteringReportRange => handle_report_range_input(key, app),
+        State::EnteringExportPath => handle_export_input(key, app),
+        State::EnteringCalendarPath => handle_calendar_input(key, app),
+        State::ReviewingImport => handle_review(key, app),
         _ => (),
     }
 }
@@ -21,19 +38,37 @@ pub fn handle_browsing(key: Key, app: &mut App) {
         // TODO
         // k if common_key_events::shift_left_event(k) => app.prev_week(),
         // k if common_key_events::shift_right_event(k) => app.next_week(),
-        k if common_key_events::save_event(k) => app.write(),
+        k if common_key_events::save_event(k) => {
+            app.write();
+        }
+        k if common_key_events::toggle_comment_event(k) && app.on_group_row() => app.toggle_group(),
         k if common_key_events::toggle_comment_event(k) => app.toggle_writing_comment(),
-        k if common_key_events::inc_event(k) => app.change_hours(0.5),
-        k if common_key_events::dec_event(k) => app.change_hours(-0.5),
+        k if common_key_events::inc_event(k) => app.change_minutes(30),
+        k if common_key_events::dec_event(k) => app.change_minutes(-30),
         k if common_key_events::quit_event(k) => app.quit(),
         Key::Char('?') => app.toggle_view_controls(),
-        Key::Char(' ') => app.set_hours(7.5),
+        Key::Char(' ') => app.set_minutes(app.conf.norm_minutes()),
+        Key::Char('i') => app.toggle_entering_duration(),
+        Key::Char('e') => app.toggle_editing_entries(),
+        Key::Char('t') => app.start_timer(),
+        Key::Char('T') => app.stop_timer(),
         k if common_key_events::new_timecode_event(k) => app.toggle_adding_timecode(),
+        Key::Char('z') => app.toggle_group(),
+        Key::Char('A') => app.toggle_entering_absence(),
+        Key::Char('R') => app.toggle_renaming_timecode(),
+        Key::Char('D') => app.request_delete_timecode(),
+        k if common_key_events::move_up_event(k) => app.move_timecode(-1),
+        k if common_key_events::move_down_event(k) => app.move_timecode(1),
         Key::Char('S') => app.star_timecode(),
         Key::Char('U') => app.unstar_timecode(),
-        Key::Backspace => app.set_hours(0.0),
+        Key::Char('B') => app.toggle_restoring_backup(),
+        Key::Char('r') => app.toggle_reports(),
+        Key::Char('I') => app.toggle_importing_calendar(),
+        Key::Char('u') => app.undo(),
+        Key::Ctrl('r') => app.redo(),
+        Key::Backspace => app.set_minutes(0),
         Key::Char(c) if common_key_events::num_event(c) => {
-            app.set_hours(c.to_digit(10).unwrap() as f32)
+            app.set_minutes(c.to_digit(10).unwrap() * 60)
         }
         _ => (),
     }
@@ -58,9 +93,175 @@ pub fn handle_timecode_input(key: Key, app: &mut App) {
     }
 }
 
+pub fn handle_rename_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.append_char_to_timecode_buffer(c),
+        Key::Esc => app.cancel_renaming_timecode(),
+        Key::Enter => app.toggle_renaming_timecode(),
+        Key::Backspace => app.delete_char_from_timecode_buffer(),
+        _ => (),
+    }
+}
+
+pub fn handle_absence_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.absence_buffer.push(c),
+        Key::Esc => app.cancel_entering_absence(),
+        Key::Enter => app.toggle_entering_absence(),
+        Key::Backspace => {
+            app.absence_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
+pub fn handle_duration_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.duration_buffer.push(c),
+        Key::Esc => app.cancel_entering_duration(),
+        Key::Enter => app.toggle_entering_duration(),
+        Key::Backspace => {
+            app.duration_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
+pub fn handle_entries_input(key: Key, app: &mut App) {
+    match key {
+        k if common_key_events::down_event(k) => app.next_entry(),
+        k if common_key_events::up_event(k) => app.prev_entry(),
+        Key::Char('a') => app.start_adding_entry(),
+        Key::Char('e') | Key::Enter => app.start_editing_entry(),
+        Key::Char('d') | Key::Delete => app.delete_entry(),
+        Key::Esc | Key::Char('q') => app.toggle_editing_entries(),
+        _ => (),
+    }
+}
+
+pub fn handle_entry_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.entry_buffer.push(c),
+        Key::Esc => app.cancel_editing_entry(),
+        Key::Enter => app.commit_entry(),
+        Key::Backspace => {
+            app.entry_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
 pub fn handle_controlscreen_input(key: Key, app: &mut App) {
     match key {
         Key::Esc | Key::Char('?') => app.toggle_view_controls(),
         _ => (),
     }
 }
+
+pub fn handle_backup_input(key: Key, app: &mut App) {
+    match key {
+        k if common_key_events::down_event(k) => app.next_backup(),
+        k if common_key_events::up_event(k) => app.prev_backup(),
+        Key::Enter => app.restore_backup(),
+        Key::Esc | Key::Char('B') => app.toggle_restoring_backup(),
+        _ => (),
+    }
+}
+
+pub fn handle_reports(key: Key, app: &mut App) {
+    match key {
+        k if common_key_events::down_event(k) => app.next_report_day(),
+        k if common_key_events::up_event(k) => app.prev_report_day(),
+        k if common_key_events::left_event(k) => app.shift_report(-1),
+        k if common_key_events::right_event(k) => app.shift_report(1),
+        Key::Tab => app.next_report_period(),
+        Key::Char('c') => app.toggle_entering_report_range(),
+        Key::Char('b') => app.toggle_report_breakdown(),
+        Key::Char('e') => app.start_export(ExportLayout::Long),
+        Key::Char('E') => app.start_export(ExportLayout::Wide),
+        Key::Esc | Key::Char('r') | Key::Char('q') => app.toggle_reports(),
+        _ => (),
+    }
+}
+
+pub fn handle_report_range_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.report_buffer.push(c),
+        Key::Esc => app.cancel_entering_report_range(),
+        Key::Enter => app.toggle_entering_report_range(),
+        Key::Backspace => {
+            app.report_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
+pub fn handle_export_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.export_buffer.push(c),
+        Key::Esc => app.cancel_export(),
+        Key::Enter => app.confirm_export(),
+        Key::Backspace => {
+            app.export_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
+pub fn handle_calendar_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char(c) => app.calendar_buffer.push(c),
+        Key::Esc => app.cancel_importing_calendar(),
+        Key::Enter => app.toggle_importing_calendar(),
+        Key::Backspace => {
+            app.calendar_buffer.pop();
+        }
+        _ => (),
+    }
+}
+
+pub fn handle_review(key: Key, app: &mut App) {
+    match key {
+        k if common_key_events::down_event(k) => app.next_proposal(),
+        k if common_key_events::up_event(k) => app.prev_proposal(),
+        Key::Char(' ') => app.toggle_proposal(),
+        Key::Char('a') => app.toggle_all_proposals(),
+        Key::Enter => app.confirm_import(),
+        Key::Esc | Key::Char('q') => app.cancel_importing_calendar(),
+        _ => (),
+    }
+}
+
+pub fn handle_recovery_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char('y') | Key::Char('j') | Key::Enter => app.keep_recovered_edits(),
+        Key::Char('n') | Key::Esc => app.discard_recovered_edits(),
+        _ => (),
+    }
+}
+
+pub fn handle_delete_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char('y') | Key::Char('j') | Key::Enter => app.confirm_delete_timecode(),
+        Key::Char('n') | Key::Esc => app.cancel_delete_timecode(),
+        _ => (),
+    }
+}
+
+pub fn handle_lock_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char('y') | Key::Char('j') | Key::Enter => app.continue_without_lock(),
+        Key::Char('n') | Key::Char('q') | Key::Esc => app.force_quit(),
+        _ => (),
+    }
+}
+
+pub fn handle_external_change_input(key: Key, app: &mut App) {
+    match key {
+        Key::Char('r') => app.reload_external_change(),
+        Key::Char('m') => app.merge_external_change(),
+        Key::Char('o') => app.overwrite_external_change(),
+        Key::Esc => app.cancel_external_change(),
+        _ => (),
+    }
+}
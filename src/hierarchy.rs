@@ -0,0 +1,291 @@
+use crate::data::Week;
+use std::collections::HashSet;
+
+// A line in the timecode column: either a group of timecodes sharing a prefix, or a timecode
+#[derive(Clone, PartialEq)]
+pub enum Row {
+    Group {
+        // Full prefix, e.g. "ACME/portal"
+        path: String,
+        // Shown relative to the enclosing group
+        label: String,
+        depth: usize,
+        collapsed: bool,
+        // Indices into the week of every timecode below the group
+        members: Vec<usize>,
+    },
+    Timecode {
+        // Index into the week
+        idx: usize,
+        label: String,
+        depth: usize,
+        // Innermost group the timecode is shown under
+        group: Option<String>,
+    },
+}
+
+impl Row {
+    pub fn depth(&self) -> usize {
+        match self {
+            Row::Group { depth, .. } | Row::Timecode { depth, .. } => *depth,
+        }
+    }
+
+    // Minutes logged on a day, summed over the members for a group
+    pub fn day_minutes(&self, week: &Week, day: u8) -> Option<u32> {
+        let members = match self {
+            Row::Group { members, .. } => members.clone(),
+            Row::Timecode { idx, .. } => vec![*idx],
+        };
+        let days: Vec<u32> = members
+            .iter()
+            .filter_map(|i| week.get(*i)?.get(day).map(|d| d.minutes))
+            .collect();
+        if days.is_empty() {
+            None
+        } else {
+            Some(days.iter().sum())
+        }
+    }
+
+    pub fn week_minutes(&self, week: &Week) -> u32 {
+        (0..7).filter_map(|day| self.day_minutes(week, day)).sum()
+    }
+}
+
+/* Lays out the timecodes of a week as a tree, splitting codes on `separator`.
+ * Prefixes shared by two or more timecodes become groups, placed where their
+ * first member is in the week. A chain of prefixes with the same members is
+ * shown as one group, so "ACME/portal" isn't nested under a lone "ACME".
+ * Children of collapsed groups are left out. An empty separator gives a flat list.
+ */
+pub fn build_rows(week: &Week, separator: &str, collapsed: &HashSet<String>) -> Vec<Row> {
+    let codes: Vec<&str> = week.0.iter().map(|tc| tc.timecode.as_str()).collect();
+    let members: Vec<usize> = (0..codes.len()).collect();
+    let mut rows = vec![];
+    if separator.is_empty() {
+        for idx in members {
+            rows.push(Row::Timecode {
+                idx,
+                label: codes[idx].to_string(),
+                depth: 0,
+                group: None,
+            });
+        }
+    } else {
+        push_rows(&codes, &members, None, 0, separator, collapsed, &mut rows);
+    }
+    rows
+}
+
+fn push_rows(
+    codes: &[&str],
+    members: &[usize],
+    parent: Option<&str>,
+    depth: usize,
+    separator: &str,
+    collapsed: &HashSet<String>,
+    rows: &mut Vec<Row>,
+) {
+    let prefix = parent.map(|p| format!("{}{}", p, separator));
+    let relative = |idx: usize| match &prefix {
+        Some(p) => codes[idx].strip_prefix(p.as_str()).unwrap_or(codes[idx]),
+        None => codes[idx],
+    };
+
+    // Children keyed by their next segment, in order of first appearance. A timecode
+    // equal to the group itself is a child of its own
+    let mut children: Vec<(Option<&str>, Vec<usize>)> = vec![];
+    for &idx in members {
+        let segment = if Some(codes[idx]) == parent {
+            None
+        } else {
+            relative(idx).split(separator).next()
+        };
+        match children
+            .iter_mut()
+            .find(|(s, _)| segment.is_some() && *s == segment)
+        {
+            Some((_, idxs)) => idxs.push(idx),
+            None => children.push((segment, vec![idx])),
+        }
+    }
+
+    for (segment, idxs) in children {
+        let segment = match segment {
+            Some(s) if idxs.len() > 1 => s,
+            _ => {
+                let idx = idxs[0];
+                let label = match segment {
+                    Some(_) => relative(idx),
+                    None => codes[idx].rsplit(separator).next().unwrap_or(codes[idx]),
+                };
+                rows.push(Row::Timecode {
+                    idx,
+                    label: label.to_string(),
+                    depth,
+                    group: parent.map(|p| p.to_string()),
+                });
+                continue;
+            }
+        };
+
+        let mut path = match &prefix {
+            Some(p) => format!("{}{}", p, segment),
+            None => segment.to_string(),
+        };
+        // Extends the group for as long as all members share the next segment too
+        loop {
+            let next_prefix = format!("{}{}", path, separator);
+            let mut next = idxs.iter().map(|&i| {
+                codes[i]
+                    .strip_prefix(next_prefix.as_str())
+                    .and_then(|rest| rest.split(separator).next())
+            });
+            let first = match next.next() {
+                Some(Some(s)) => s,
+                _ => break,
+            };
+            if next.all(|s| s == Some(first)) {
+                path = format!("{}{}", next_prefix, first);
+            } else {
+                break;
+            }
+        }
+
+        let is_collapsed = collapsed.contains(&path);
+        let label = match &prefix {
+            Some(p) => path.strip_prefix(p.as_str()).unwrap_or(&path).to_string(),
+            None => path.clone(),
+        };
+        rows.push(Row::Group {
+            path: path.clone(),
+            label,
+            depth,
+            collapsed: is_collapsed,
+            members: idxs.clone(),
+        });
+        if !is_collapsed {
+            push_rows(
+                codes,
+                &idxs,
+                Some(&path),
+                depth + 1,
+                separator,
+                collapsed,
+                rows,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Timecode;
+
+    fn week(codes: &[&str]) -> Week {
+        Week(
+            codes
+                .iter()
+                .map(|tc| Timecode::from_string(tc.to_string()))
+                .collect(),
+        )
+    }
+
+    // Rows as (depth, label), with groups marked by a trailing '/'
+    fn layout(rows: &[Row]) -> Vec<(usize, String)> {
+        rows.iter()
+            .map(|row| match row {
+                Row::Group { label, depth, .. } => (*depth, format!("{}/", label)),
+                Row::Timecode { label, depth, .. } => (*depth, label.clone()),
+            })
+            .collect()
+    }
+
+    fn rows(codes: &[&str], collapsed: &[&str]) -> Vec<(usize, String)> {
+        let collapsed = collapsed.iter().map(|c| c.to_string()).collect();
+        layout(&build_rows(&week(codes), "/", &collapsed))
+    }
+
+    fn expected(rows: &[(usize, &str)]) -> Vec<(usize, String)> {
+        rows.iter().map(|(d, l)| (*d, l.to_string())).collect()
+    }
+
+    #[test]
+    fn shared_prefixes_become_groups() {
+        let codes = ["ACME/portal/dev", "Internal", "ACME/portal/qa", "ACME/ops"];
+        assert_eq!(
+            rows(&codes, &[]),
+            expected(&[
+                (0, "ACME/"),
+                (1, "portal/"),
+                (2, "dev"),
+                (2, "qa"),
+                (1, "ops"),
+                (0, "Internal"),
+            ])
+        );
+    }
+
+    #[test]
+    fn chains_with_the_same_members_are_one_group() {
+        let codes = ["ACME/portal/dev", "ACME/portal/qa", "Other/thing"];
+        assert_eq!(
+            rows(&codes, &[]),
+            expected(&[
+                (0, "ACME/portal/"),
+                (1, "dev"),
+                (1, "qa"),
+                (0, "Other/thing"),
+            ])
+        );
+    }
+
+    #[test]
+    fn timecode_equal_to_its_group_is_a_child() {
+        let codes = ["ACME", "ACME/dev"];
+        assert_eq!(
+            rows(&codes, &[]),
+            expected(&[(0, "ACME/"), (1, "ACME"), (1, "dev")])
+        );
+    }
+
+    #[test]
+    fn collapsed_groups_hide_their_children() {
+        let codes = ["ACME/portal/dev", "ACME/portal/qa", "ACME/ops", "Internal"];
+        assert_eq!(
+            rows(&codes, &["ACME/portal"]),
+            expected(&[(0, "ACME/"), (1, "portal/"), (1, "ops"), (0, "Internal")])
+        );
+        assert_eq!(
+            rows(&codes, &["ACME"]),
+            expected(&[(0, "ACME/"), (0, "Internal")])
+        );
+    }
+
+    #[test]
+    fn group_members_and_parents_are_recorded() {
+        let built = build_rows(&week(&["A/x", "B", "A/y"]), "/", &HashSet::new());
+        match &built[0] {
+            Row::Group { path, members, .. } => {
+                assert_eq!(path, "A");
+                assert_eq!(members, &[0, 2]);
+            }
+            _ => panic!("expected a group"),
+        }
+        match &built[2] {
+            Row::Timecode { idx, group, .. } => {
+                assert_eq!(*idx, 2);
+                assert_eq!(group.as_deref(), Some("A"));
+            }
+            _ => panic!("expected a timecode"),
+        }
+    }
+
+    #[test]
+    fn empty_separator_gives_a_flat_list() {
+        let built = build_rows(&week(&["A/x", "A/y"]), "", &HashSet::new());
+        assert_eq!(layout(&built), expected(&[(0, "A/x"), (0, "A/y")]));
+    }
+}
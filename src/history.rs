@@ -0,0 +1,368 @@
+use crate::config::TimecodeInfo;
+use crate::data::{Absence, Day, RunningTimer, Week};
+use crate::journal::JournalEntry;
+use chrono::NaiveDate;
+
+// Edits kept for undo; the oldest are dropped beyond this
+const HISTORY_LIMIT: usize = 200;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditKind {
+    Hours,
+    Comment,
+    Entries,
+    NewTimecode,
+    Star,
+    Unstar,
+    Timer,
+    Rename,
+    Delete,
+    Move,
+    Absence,
+    Import,
+}
+
+// A single mutation, holding the state on both sides of it
+pub enum Change {
+    Day {
+        year: usize,
+        week: u8,
+        timecode: String,
+        day: u8,
+        before: Option<Day>,
+        after: Option<Day>,
+    },
+    AddTimecode {
+        year: usize,
+        week: u8,
+        timecode: String,
+    },
+    // Whole weeks, for edits that touch many cells
+    Weeks {
+        before: Vec<(usize, u8, Week)>,
+        after: Vec<(usize, u8, Week)>,
+    },
+    Starred {
+        before: Vec<String>,
+        after: Vec<String>,
+    },
+    Registry {
+        timecode: String,
+        before: Option<TimecodeInfo>,
+        after: Option<TimecodeInfo>,
+    },
+    Timer {
+        before: Option<RunningTimer>,
+        after: Option<RunningTimer>,
+    },
+    Absence {
+        date: NaiveDate,
+        before: Option<Absence>,
+        after: Option<Absence>,
+    },
+}
+
+impl Change {
+    // Journal entries that bring the data to the state after (or before, when undoing) the change
+    pub fn journal_entries(&self, undo: bool) -> Vec<JournalEntry> {
+        match self {
+            Change::Day {
+                year,
+                week,
+                timecode,
+                day,
+                before,
+                after,
+            } => {
+                let value = if undo { before } else { after };
+                vec![match value {
+                    Some(v) => JournalEntry::SetDay {
+                        year: *year,
+                        week: *week,
+                        timecode: timecode.clone(),
+                        day: *day,
+                        value: v.clone(),
+                    },
+                    None => JournalEntry::ClearDay {
+                        year: *year,
+                        week: *week,
+                        timecode: timecode.clone(),
+                        day: *day,
+                    },
+                }]
+            }
+            Change::AddTimecode {
+                year,
+                week,
+                timecode,
+            } => vec![if undo {
+                JournalEntry::RemoveTimecode {
+                    year: *year,
+                    week: *week,
+                    timecode: timecode.clone(),
+                }
+            } else {
+                JournalEntry::AddTimecode {
+                    year: *year,
+                    week: *week,
+                    timecode: timecode.clone(),
+                }
+            }],
+            Change::Weeks { before, after } => {
+                let weeks = if undo { before } else { after };
+                weeks
+                    .iter()
+                    .map(|(year, week, value)| JournalEntry::SetWeek {
+                        year: *year,
+                        week: *week,
+                        value: value.clone(),
+                    })
+                    .collect()
+            }
+            // Starred timecodes and the registry live in the config, which is written right away
+            Change::Starred { .. } | Change::Registry { .. } => vec![],
+            Change::Timer { before, after } => vec![JournalEntry::SetTimer(if undo {
+                before.clone()
+            } else {
+                after.clone()
+            })],
+            Change::Absence {
+                date,
+                before,
+                after,
+            } => vec![JournalEntry::SetAbsence {
+                date: *date,
+                value: if undo { before.clone() } else { after.clone() },
+            }],
+        }
+    }
+}
+
+// One undo step. Changes are applied in order, and reverted in reverse order
+pub struct Edit {
+    pub kind: EditKind,
+    pub timecode: String,
+    pub day: Option<u8>,
+    pub changes: Vec<Change>,
+}
+
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    // Whether the last edit may still absorb the next one
+    open: bool,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: vec![],
+            redo: vec![],
+            open: false,
+        }
+    }
+
+    /* Records a new edit, which makes the undone edits unreachable. With `group`,
+     * consecutive edits of the same kind to the same cell become one step, so a
+     * comment is undone as a whole rather than a character at a time.
+     */
+    pub fn push(&mut self, edit: Edit, group: bool) {
+        self.redo.clear();
+        if group && self.open {
+            if let Some(last) = self.undo.last_mut() {
+                if last.kind == edit.kind && last.timecode == edit.timecode && last.day == edit.day
+                {
+                    merge_changes(&mut last.changes, edit.changes);
+                    return;
+                }
+            }
+        }
+        self.undo.push(edit);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.open = group;
+    }
+
+    // Makes the next edit start a step of its own
+    pub fn close_group(&mut self) {
+        self.open = false;
+    }
+
+    pub fn take_undo(&mut self) -> Option<Edit> {
+        self.open = false;
+        self.undo.pop()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    pub fn undone(&mut self, edit: Edit) {
+        self.redo.push(edit);
+    }
+
+    pub fn redone(&mut self, edit: Edit) {
+        self.undo.push(edit);
+    }
+
+    // Used when the data is replaced wholesale, e.g. by a reload or a restored backup
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open = false;
+    }
+}
+
+// Keeps the earliest `before` and takes the latest `after` of changes to the same cell
+fn merge_changes(changes: &mut Vec<Change>, new: Vec<Change>) {
+    for change in new {
+        let existing = changes.iter_mut().find(|c| match (c, &change) {
+            (
+                Change::Day {
+                    year,
+                    week,
+                    timecode,
+                    day,
+                    ..
+                },
+                Change::Day {
+                    year: y,
+                    week: w,
+                    timecode: tc,
+                    day: d,
+                    ..
+                },
+            ) => year == y && week == w && timecode == tc && day == d,
+            _ => false,
+        });
+        match (existing, change) {
+            (Some(Change::Day { after, .. }), Change::Day { after: new, .. }) => *after = new,
+            (_, change) => changes.push(change),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(kind: EditKind, day: u8, before: u32, after: u32) -> Edit {
+        Edit {
+            kind,
+            timecode: String::from("ACME"),
+            day: Some(day),
+            changes: vec![Change::Day {
+                year: 2026,
+                week: 42,
+                timecode: String::from("ACME"),
+                day,
+                before: Some(Day::new(before)),
+                after: Some(Day::new(after)),
+            }],
+        }
+    }
+
+    // Minutes before and after each change of an edit
+    fn minutes(edit: &Edit) -> Vec<(u32, u32)> {
+        edit.changes
+            .iter()
+            .map(|c| match c {
+                Change::Day { before, after, .. } => (
+                    before.as_ref().map(|d| d.minutes).unwrap_or(0),
+                    after.as_ref().map(|d| d.minutes).unwrap_or(0),
+                ),
+                _ => panic!("expected a day change"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grouped_edits_to_one_cell_are_one_step() {
+        let mut history = History::new();
+        history.push(edit(EditKind::Comment, 0, 0, 1), true);
+        history.push(edit(EditKind::Comment, 0, 1, 2), true);
+        history.push(edit(EditKind::Comment, 0, 2, 3), true);
+
+        let step = history.take_undo().unwrap();
+        assert_eq!(minutes(&step), [(0, 3)]);
+        assert!(history.take_undo().is_none());
+    }
+
+    #[test]
+    fn groups_end_at_another_cell_kind_or_close() {
+        let mut history = History::new();
+        history.push(edit(EditKind::Comment, 0, 0, 1), true);
+        history.push(edit(EditKind::Comment, 1, 0, 1), true);
+        history.push(edit(EditKind::Hours, 1, 1, 2), true);
+        history.close_group();
+        history.push(edit(EditKind::Hours, 1, 2, 3), true);
+        // Ungrouped edits never absorb the next one
+        history.push(edit(EditKind::Hours, 1, 3, 4), false);
+        history.push(edit(EditKind::Hours, 1, 4, 5), true);
+
+        let mut steps = vec![];
+        while let Some(step) = history.take_undo() {
+            steps.push(minutes(&step));
+        }
+        assert_eq!(
+            steps,
+            [
+                vec![(4, 5)],
+                vec![(3, 4)],
+                vec![(2, 3)],
+                vec![(1, 2)],
+                vec![(0, 1)],
+                vec![(0, 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn undo_closes_the_group() {
+        let mut history = History::new();
+        history.push(edit(EditKind::Comment, 0, 0, 1), true);
+        let step = history.take_undo().unwrap();
+        history.redone(step);
+        history.push(edit(EditKind::Comment, 0, 1, 2), true);
+        assert_eq!(minutes(&history.take_undo().unwrap()), [(1, 2)]);
+        assert_eq!(minutes(&history.take_undo().unwrap()), [(0, 1)]);
+    }
+
+    #[test]
+    fn undo_and_redo_move_steps_between_stacks() {
+        let mut history = History::new();
+        history.push(edit(EditKind::Hours, 0, 0, 1), false);
+        history.push(edit(EditKind::Hours, 0, 1, 2), false);
+
+        let step = history.take_undo().unwrap();
+        assert_eq!(minutes(&step), [(1, 2)]);
+        history.undone(step);
+        let step = history.take_redo().unwrap();
+        assert_eq!(minutes(&step), [(1, 2)]);
+        history.redone(step);
+        assert!(history.take_redo().is_none());
+
+        // A new edit after undoing drops what could be redone
+        let step = history.take_undo().unwrap();
+        history.undone(step);
+        history.push(edit(EditKind::Hours, 0, 1, 5), false);
+        assert!(history.take_redo().is_none());
+    }
+
+    #[test]
+    fn oldest_edits_are_dropped_beyond_the_limit() {
+        let mut history = History::new();
+        for i in 0..HISTORY_LIMIT as u32 + 5 {
+            history.push(edit(EditKind::Hours, 0, i, i + 1), false);
+        }
+        let mut count = 0;
+        let mut oldest = None;
+        while let Some(step) = history.take_undo() {
+            count += 1;
+            oldest = Some(minutes(&step));
+        }
+        assert_eq!(count, HISTORY_LIMIT);
+        assert_eq!(oldest, Some(vec![(5, 6)]));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::Language;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+// Countries with a built-in calendar, by the code used in the config
+pub const COUNTRIES: [&str; 1] = ["NO"];
+
+/* A holiday from the custom holidays file. `date` is either "YYYY-MM-DD" for a
+ * single day, or "MM-DD" for a day that recurs every year.
+ */
+#[derive(Deserialize)]
+pub struct CustomHoliday {
+    pub date: String,
+    pub name: String,
+}
+
+// Holidays that are days off, from the built-in country calendars and the custom file
+#[derive(Default)]
+pub struct Calendar {
+    countries: Vec<String>,
+    custom: Vec<CustomHoliday>,
+}
+
+impl Calendar {
+    pub fn new(countries: &[String], custom_file: Option<&Path>) -> Result<Calendar, String> {
+        for country in countries {
+            if !COUNTRIES.contains(&country.as_str()) {
+                return Err(format!(
+                    "No holiday calendar for {}, choose from {}",
+                    country,
+                    COUNTRIES.join(", ")
+                ));
+            }
+        }
+        let custom = match custom_file {
+            Some(path) => {
+                let text = fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+                let custom: Vec<CustomHoliday> = serde_json::from_str(&text)
+                    .map_err(|e| format!("Invalid holidays file {}: {}", path.display(), e))?;
+                for h in custom.iter() {
+                    // A leap year, so "02-29" is accepted
+                    let one_off = NaiveDate::parse_from_str(&h.date, "%Y-%m-%d").is_ok();
+                    if !one_off && custom_date(&h.date, 2000).is_none() {
+                        return Err(format!(
+                            "Invalid holiday date {}, use YYYY-MM-DD or MM-DD",
+                            h.date
+                        ));
+                    }
+                }
+                custom
+            }
+            None => vec![],
+        };
+        Ok(Calendar {
+            countries: countries.to_vec(),
+            custom,
+        })
+    }
+
+    // Name of the holiday on a date, if it is one
+    pub fn holiday(&self, date: NaiveDate, lang: &Language) -> Option<String> {
+        let custom = self
+            .custom
+            .iter()
+            .find(|h| custom_date(&h.date, date.year()) == Some(date));
+        if let Some(h) = custom {
+            return Some(h.name.clone());
+        }
+        self.countries
+            .iter()
+            .flat_map(|c| country_holidays(c, date.year()))
+            .find(|(d, _)| *d == date)
+            .map(|(_, names)| match lang {
+                Language::English => names.0.to_string(),
+                Language::Norsk => names.1.to_string(),
+            })
+    }
+
+    // Every holiday in a year, ordered by date
+    pub fn holidays_in(&self, year: i32, lang: &Language) -> Vec<(NaiveDate, String)> {
+        let mut dates: Vec<NaiveDate> = self
+            .countries
+            .iter()
+            .flat_map(|c| country_holidays(c, year))
+            .map(|(d, _)| d)
+            .chain(
+                self.custom
+                    .iter()
+                    .filter_map(|h| custom_date(&h.date, year)),
+            )
+            .collect();
+        dates.sort();
+        dates.dedup();
+        dates
+            .into_iter()
+            .filter_map(|d| Some((d, self.holiday(d, lang)?)))
+            .collect()
+    }
+}
+
+// The date of a custom holiday in `year`, None if a one-off holiday is in another year
+fn custom_date(date: &str, year: i32) -> Option<NaiveDate> {
+    if let Ok(d) = NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        return Some(d).filter(|d| d.year() == year);
+    }
+    NaiveDate::parse_from_str(&format!("{}-{}", year, date), "%Y-%m-%d").ok()
+}
+
+// English and Norwegian names of a holiday
+type Names = (&'static str, &'static str);
+
+fn country_holidays(country: &str, year: i32) -> Vec<(NaiveDate, Names)> {
+    match country {
+        "NO" => norway(year),
+        _ => vec![],
+    }
+}
+
+fn norway(year: i32) -> Vec<(NaiveDate, Names)> {
+    let easter = easter_sunday(year);
+    let fixed = |month, day| NaiveDate::from_ymd(year, month, day);
+    let from_easter = |days| easter + Duration::days(days);
+    vec![
+        (fixed(1, 1), ("New Year's Day", "Første nyttårsdag")),
+        (from_easter(-3), ("Maundy Thursday", "Skjærtorsdag")),
+        (from_easter(-2), ("Good Friday", "Langfredag")),
+        (easter, ("Easter Sunday", "Første påskedag")),
+        (from_easter(1), ("Easter Monday", "Andre påskedag")),
+        (fixed(5, 1), ("Labour Day", "Arbeidernes dag")),
+        (fixed(5, 17), ("Constitution Day", "Grunnlovsdag")),
+        (from_easter(39), ("Ascension Day", "Kristi himmelfartsdag")),
+        (from_easter(49), ("Whit Sunday", "Første pinsedag")),
+        (from_easter(50), ("Whit Monday", "Andre pinsedag")),
+        (fixed(12, 25), ("Christmas Day", "Første juledag")),
+        (fixed(12, 26), ("Boxing Day", "Andre juledag")),
+    ]
+}
+
+// Easter Sunday in the Gregorian calendar, by the anonymous Gregorian algorithm
+pub fn easter_sunday(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd(year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_easter_sunday() {
+        let cases = [(2024, 3, 31), (2025, 4, 20), (2038, 4, 25)];
+        for (year, month, day) in cases {
+            assert_eq!(
+                easter_sunday(year),
+                NaiveDate::from_ymd(year, month, day),
+                "{}",
+                year
+            );
+        }
+    }
+
+    #[test]
+    fn norway_has_holidays_counted_from_easter() {
+        let calendar = Calendar::new(&[String::from("NO")], None).unwrap();
+        let cases = [
+            ((4, 2), "Maundy Thursday"),
+            ((5, 14), "Ascension Day"),
+            ((5, 25), "Whit Monday"),
+        ];
+        for ((month, day), name) in cases {
+            let date = NaiveDate::from_ymd(2026, month, day);
+            let holiday = calendar.holiday(date, &Language::English);
+            assert_eq!(holiday.as_deref(), Some(name));
+        }
+        let day_after = NaiveDate::from_ymd(2026, 5, 26);
+        assert!(calendar.holiday(day_after, &Language::English).is_none());
+    }
+}
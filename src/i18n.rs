@@ -1,4 +1,8 @@
 use crate::config::Language;
+use crate::data::AbsenceKind;
+use crate::history::EditKind;
+use crate::import::OnConflict;
+use crate::report::{Breakdown, Period};
 
 // Internationalization and localization
 pub struct I18n;
@@ -31,22 +35,32 @@ impl I18n {
             Language::Norsk => "Uke",
         }
     }
-    pub fn key_labels(lang: &Language) -> Vec<&'static str> {
-        match lang {
-            _ => vec![
-                "?",
-                "hjkl / ←↓↑→",
-                "K / Shift + ↑",
-                "J / Shift + ↓",
-                "Enter",
-                "N",
-                "S",
-                "U",
-                "q / Esc",
-                "Ctrl + C",
-                "w",
-            ],
-        }
+    pub fn key_labels(_lang: &Language) -> Vec<&'static str> {
+        vec![
+            "?",
+            "hjkl / ←↓↑→",
+            "K / Shift + ↑",
+            "J / Shift + ↓",
+            "0-9 / i",
+            "e",
+            "t / T",
+            "Enter",
+            "N",
+            "R",
+            "D",
+            "Alt + k / Alt + j",
+            "z",
+            "A",
+            "S",
+            "U",
+            "q / Esc",
+            "Ctrl + C",
+            "w",
+            "u / Ctrl + r",
+            "B",
+            "r",
+            "I",
+        ]
     }
     pub fn action_labels(lang: &Language) -> Vec<&'static str> {
         match lang {
@@ -55,26 +69,50 @@ impl I18n {
                 "Movement",
                 "Increment hours",
                 "Decrement hours",
+                "Set hours / Type hours (1.5 or 1:30)",
+                "Time entries (a: add, e: edit, d: delete)",
+                "Start / stop timer",
                 "Write comment",
                 "New timecode",
+                "Rename timecode everywhere",
+                "Delete timecode from week",
+                "Move timecode up / down",
+                "Collapse / expand group (also Enter on a group)",
+                "Absence for the day",
                 "Star timecode",
                 "Unstar timecode",
                 "Quit (Saves on exit)",
                 "Force quit",
                 "Save",
+                "Undo / redo",
+                "Restore backup",
+                "Reports (Tab: period, ←→: back / forward, c: custom range, b: chart by client, e / E: export rows / weekly grid)",
+                "Import the week's events from a calendar (.ics)",
             ],
             Language::Norsk => vec![
                 "Vis denne menyen",
                 "Bevegelse",
                 "Inkrementer timer",
                 "Dekrementer timer",
+                "Sett timer / Skriv timer (1.5 eller 1:30)",
+                "Tidsoppføringer (a: legg til, e: endre, d: slett)",
+                "Start / stopp tidtaker",
                 "Skriv kommentar",
                 "Ny timekode",
+                "Gi timekoden nytt navn overalt",
+                "Slett timekode fra uken",
+                "Flytt timekode opp / ned",
+                "Slå sammen / utvid gruppe (også Enter på en gruppe)",
+                "Fravær for dagen",
                 "Legg til timekode som favoritt",
                 "Fjern timekode som favoritt",
                 "Avslutt (Lagrer ved avslutning)",
                 "Tvangsavslutt",
                 "Lagre",
+                "Angre / gjør om",
+                "Gjenopprett sikkerhetskopi",
+                "Rapporter (Tab: periode, ←→: tilbake / frem, c: egen periode, b: diagram per kunde, e / E: eksporter rader / ukerutenett)",
+                "Importer ukens hendelser fra en kalender (.ics)",
             ],
         }
     }
@@ -84,4 +122,535 @@ impl I18n {
             Language::Norsk => "Trykk på ? for å vise kontrollene",
         }
     }
+    pub fn saved_message(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Saved",
+            Language::Norsk => "Lagret",
+        }
+    }
+    pub fn save_failed(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Unable to save",
+            Language::Norsk => "Kunne ikke lagre",
+        }
+    }
+    pub fn backups_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Backups (Enter to restore, Esc to cancel)",
+            Language::Norsk => "Sikkerhetskopier (Enter for å gjenopprette, Esc for å avbryte)",
+        }
+    }
+    pub fn no_backups(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "No backups yet",
+            Language::Norsk => "Ingen sikkerhetskopier ennå",
+        }
+    }
+    pub fn restored_message(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Restored",
+            Language::Norsk => "Gjenopprettet",
+        }
+    }
+    pub fn restore_failed(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Unable to restore backup",
+            Language::Norsk => "Kunne ikke gjenopprette sikkerhetskopi",
+        }
+    }
+    pub fn journal_failed(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Unable to write journal",
+            Language::Norsk => "Kunne ikke skrive journal",
+        }
+    }
+    pub fn recovery_prompt(lang: &Language, edits: usize) -> String {
+        match lang {
+            Language::English => format!(
+                "Recovered {} unsaved edits from last session. Keep them? (y/n)",
+                edits
+            ),
+            Language::Norsk => format!(
+                "Gjenopprettet {} ulagrede endringer fra forrige økt. Behold dem? (j/n)",
+                edits
+            ),
+        }
+    }
+    pub fn lock_prompt(lang: &Language, owner: &str) -> String {
+        match lang {
+            Language::English => format!(
+                "Data file is in use by another timekeeper ({}). Continue anyway? (y/n)",
+                owner
+            ),
+            Language::Norsk => format!(
+                "Datafilen brukes av en annen timekeeper ({}). Fortsette likevel? (j/n)",
+                owner
+            ),
+        }
+    }
+    pub fn external_change_prompt(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => {
+                "Data file was changed on disk. (r)eload, (m)erge, (o)verwrite or Esc to cancel"
+            }
+            Language::Norsk => {
+                "Datafilen er endret på disk. (r) last inn, (m) flett, (o) overskriv eller Esc for å avbryte"
+            }
+        }
+    }
+    pub fn reloaded_message(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Reloaded from disk",
+            Language::Norsk => "Lastet inn på nytt fra disk",
+        }
+    }
+    pub fn invalid_duration(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Invalid duration, use 1.5 or 1:30",
+            Language::Norsk => "Ugyldig varighet, bruk 1.5 eller 1:30",
+        }
+    }
+    pub fn entries_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Entries (a: add, e: edit, d: delete, Esc: close)",
+            Language::Norsk => "Oppføringer (a: legg til, e: endre, d: slett, Esc: lukk)",
+        }
+    }
+    pub fn entry_input_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Start-end and note, e.g. 09:00-10:30 Standup",
+            Language::Norsk => "Start-slutt og notat, f.eks. 09:00-10:30 Standup",
+        }
+    }
+    pub fn invalid_entry(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Invalid entry, use 09:00-10:30 note",
+            Language::Norsk => "Ugyldig oppføring, bruk 09:00-10:30 notat",
+        }
+    }
+    pub fn hours_from_entries(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Hours come from the entries of this day, edit them with e",
+            Language::Norsk => "Timene kommer fra oppføringene denne dagen, endre dem med e",
+        }
+    }
+    pub fn timer_started(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Timer started, stop it with T",
+            Language::Norsk => "Tidtaker startet, stopp den med T",
+        }
+    }
+    pub fn timer_stopped(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Timer stopped",
+            Language::Norsk => "Tidtaker stoppet",
+        }
+    }
+    pub fn total_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Total",
+            Language::Norsk => "Totalt",
+        }
+    }
+    pub fn flex_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Flex",
+            Language::Norsk => "Fleks",
+        }
+    }
+    pub fn logged_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Logged",
+            Language::Norsk => "Ført",
+        }
+    }
+    pub fn absent_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Absent",
+            Language::Norsk => "Fravær",
+        }
+    }
+    pub fn expected_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Expected",
+            Language::Norsk => "Forventet",
+        }
+    }
+    pub fn adjusted_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Adjusted",
+            Language::Norsk => "Justert",
+        }
+    }
+    pub fn logged_message(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Logged",
+            Language::Norsk => "Førte",
+        }
+    }
+    pub fn undone(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Undid",
+            Language::Norsk => "Angret",
+        }
+    }
+    pub fn redone(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Redid",
+            Language::Norsk => "Gjorde om",
+        }
+    }
+    pub fn nothing_to_undo(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Nothing to undo",
+            Language::Norsk => "Ingenting å angre",
+        }
+    }
+    pub fn nothing_to_redo(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Nothing to redo",
+            Language::Norsk => "Ingenting å gjøre om",
+        }
+    }
+    pub fn edit_label(lang: &Language, kind: EditKind) -> &'static str {
+        match lang {
+            Language::English => match kind {
+                EditKind::Hours => "hours",
+                EditKind::Comment => "comment",
+                EditKind::Entries => "entries",
+                EditKind::NewTimecode => "new timecode",
+                EditKind::Star => "star",
+                EditKind::Unstar => "unstar",
+                EditKind::Timer => "timer",
+                EditKind::Rename => "rename",
+                EditKind::Delete => "delete",
+                EditKind::Move => "move",
+                EditKind::Absence => "absence",
+                EditKind::Import => "import",
+            },
+            Language::Norsk => match kind {
+                EditKind::Hours => "timer",
+                EditKind::Comment => "kommentar",
+                EditKind::Entries => "oppføringer",
+                EditKind::NewTimecode => "ny timekode",
+                EditKind::Star => "favoritt",
+                EditKind::Unstar => "fjern favoritt",
+                EditKind::Timer => "tidtaker",
+                EditKind::Rename => "nytt navn",
+                EditKind::Delete => "slett",
+                EditKind::Move => "flytt",
+                EditKind::Absence => "fravær",
+                EditKind::Import => "import",
+            },
+        }
+    }
+    pub fn rename_prompt(lang: &Language, from: &str) -> String {
+        match lang {
+            Language::English => format!("Rename {} everywhere to: ", from),
+            Language::Norsk => format!("Gi {} nytt navn overalt: ", from),
+        }
+    }
+    pub fn renamed_message(lang: &Language, from: &str, to: &str, merged: bool) -> String {
+        match (lang, merged) {
+            (Language::English, false) => format!("Renamed {} to {}", from, to),
+            (Language::English, true) => format!("Merged {} into {}", from, to),
+            (Language::Norsk, false) => format!("Endret navn på {} til {}", from, to),
+            (Language::Norsk, true) => format!("Slo sammen {} med {}", from, to),
+        }
+    }
+    pub fn delete_prompt(lang: &Language, timecode: &str, hours: &str) -> String {
+        match lang {
+            Language::English => format!(
+                "Delete {} with {} hours from this week? (y/n)",
+                timecode, hours
+            ),
+            Language::Norsk => format!(
+                "Slette {} med {} timer fra denne uken? (j/n)",
+                timecode, hours
+            ),
+        }
+    }
+    pub fn deleted_message(lang: &Language, timecode: &str) -> String {
+        match lang {
+            Language::English => format!("Deleted {} from this week, undo with u", timecode),
+            Language::Norsk => format!("Slettet {} fra denne uken, angre med u", timecode),
+        }
+    }
+    pub fn absence_label(lang: &Language, kind: AbsenceKind) -> &'static str {
+        match lang {
+            Language::English => match kind {
+                AbsenceKind::Vacation => "Vacation",
+                AbsenceKind::SickLeave => "Sick leave",
+                AbsenceKind::ChildSick => "Sick child",
+                AbsenceKind::ParentalLeave => "Parental leave",
+                AbsenceKind::UnpaidLeave => "Unpaid leave",
+            },
+            Language::Norsk => match kind {
+                AbsenceKind::Vacation => "Ferie",
+                AbsenceKind::SickLeave => "Sykefravær",
+                AbsenceKind::ChildSick => "Sykt barn",
+                AbsenceKind::ParentalLeave => "Foreldrepermisjon",
+                AbsenceKind::UnpaidLeave => "Ulønnet permisjon",
+            },
+        }
+    }
+    pub fn absence_prompt(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => {
+                "Absence (v: vacation, s: sick, c: sick child, p: parental, u: unpaid), \
+                 then hours for part of a day. Empty clears: "
+            }
+            Language::Norsk => {
+                "Fravær (v: ferie, s: syk, c: sykt barn, p: foreldrepermisjon, u: ulønnet), \
+                 så timer for en del av dagen. Tomt fjerner: "
+            }
+        }
+    }
+    pub fn invalid_absence(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Unknown absence, use v, s, c, p or u followed by optional hours",
+            Language::Norsk => "Ukjent fravær, bruk v, s, c, p eller u etterfulgt av timer",
+        }
+    }
+    pub fn vacation_balance(lang: &Language, used: f64, allowance: f64) -> String {
+        let days = |d: f64| {
+            let d = format!("{:.1}", d);
+            d.trim_end_matches(".0").to_string()
+        };
+        match lang {
+            Language::English => format!(
+                "Vacation: {} of {} days taken, {} left",
+                days(used),
+                days(allowance),
+                days(allowance - used)
+            ),
+            Language::Norsk => format!(
+                "Ferie: {} av {} dager tatt ut, {} igjen",
+                days(used),
+                days(allowance),
+                days(allowance - used)
+            ),
+        }
+    }
+    pub fn reports_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Report",
+            Language::Norsk => "Rapport",
+        }
+    }
+    pub fn period_label(lang: &Language, period: Period) -> &'static str {
+        match lang {
+            Language::English => match period {
+                Period::Week => "Week",
+                Period::Month => "Month",
+                Period::Quarter => "Quarter",
+                Period::Year => "Year",
+                Period::Custom => "Custom",
+            },
+            Language::Norsk => match period {
+                Period::Week => "Uke",
+                Period::Month => "Måned",
+                Period::Quarter => "Kvartal",
+                Period::Year => "År",
+                Period::Custom => "Egen periode",
+            },
+        }
+    }
+    pub fn breakdown_label(lang: &Language, breakdown: Breakdown) -> &'static str {
+        match lang {
+            Language::English => match breakdown {
+                Breakdown::Timecodes => "Timecode",
+                Breakdown::Clients => "Client",
+            },
+            Language::Norsk => match breakdown {
+                Breakdown::Timecodes => "Timekode",
+                Breakdown::Clients => "Kunde",
+            },
+        }
+    }
+    pub fn day_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Day",
+            Language::Norsk => "Dag",
+        }
+    }
+    pub fn no_client(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "(no client)",
+            Language::Norsk => "(ingen kunde)",
+        }
+    }
+    pub fn no_hours(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "No hours logged",
+            Language::Norsk => "Ingen timer ført",
+        }
+    }
+    pub fn reports_help(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => {
+                "Tab: period, ←→: back / forward, c: custom range, b: chart by client, e / E: export, Esc: close"
+            }
+            Language::Norsk => {
+                "Tab: periode, ←→: tilbake / frem, c: egen periode, b: diagram per kunde, e / E: eksporter, Esc: lukk"
+            }
+        }
+    }
+    pub fn range_prompt(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "From and to date (YYYY-MM-DD YYYY-MM-DD): ",
+            Language::Norsk => "Fra og til dato (ÅÅÅÅ-MM-DD ÅÅÅÅ-MM-DD): ",
+        }
+    }
+    pub fn invalid_range(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Invalid range, use YYYY-MM-DD YYYY-MM-DD with the earliest first",
+            Language::Norsk => {
+                "Ugyldig periode, bruk ÅÅÅÅ-MM-DD ÅÅÅÅ-MM-DD med den tidligste først"
+            }
+        }
+    }
+    pub fn exported_message(lang: &Language, count: usize, path: &str, events: bool) -> String {
+        match lang {
+            Language::English => {
+                let noun = match (events, count == 1) {
+                    (false, true) => "row",
+                    (false, false) => "rows",
+                    (true, true) => "event",
+                    (true, false) => "events",
+                };
+                format!("Exported {} {} to {}", count, noun, path)
+            }
+            Language::Norsk => {
+                let noun = match (events, count == 1) {
+                    (false, true) => "rad",
+                    (false, false) => "rader",
+                    (true, true) => "hendelse",
+                    (true, false) => "hendelser",
+                };
+                format!("Eksporterte {} {} til {}", count, noun, path)
+            }
+        }
+    }
+    pub fn export_prompt(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Export to (.csv, .xlsx, .ics, .timeclock or .org): ",
+            Language::Norsk => "Eksporter til (.csv, .xlsx, .ics, .timeclock eller .org): ",
+        }
+    }
+    pub fn imported_message(
+        lang: &Language,
+        dry_run: bool,
+        days: usize,
+        hours: &str,
+        timecodes: usize,
+    ) -> String {
+        match lang {
+            Language::English => format!(
+                "{} {} hours on {} days in {} timecodes",
+                if dry_run { "Would import" } else { "Imported" },
+                hours,
+                days,
+                timecodes
+            ),
+            Language::Norsk => format!(
+                "{} {} timer på {} dager i {} timekoder",
+                if dry_run {
+                    "Ville importert"
+                } else {
+                    "Importerte"
+                },
+                hours,
+                days,
+                timecodes
+            ),
+        }
+    }
+    pub fn conflict_label(lang: &Language, on_conflict: OnConflict) -> &'static str {
+        match lang {
+            Language::English => match on_conflict {
+                OnConflict::Skip => "already logged, skipped",
+                OnConflict::Replace => "already logged, replaced",
+                OnConflict::Add => "already logged, added",
+            },
+            Language::Norsk => match on_conflict {
+                OnConflict::Skip => "allerede ført, hoppet over",
+                OnConflict::Replace => "allerede ført, erstattet",
+                OnConflict::Add => "allerede ført, lagt til",
+            },
+        }
+    }
+    pub fn calendar_prompt(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Import the week's events from (.ics): ",
+            Language::Norsk => "Importer ukens hendelser fra (.ics): ",
+        }
+    }
+    pub fn no_events(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "No events in the week match a calendar rule",
+            Language::Norsk => "Ingen hendelser i uken passer med en kalenderregel",
+        }
+    }
+    pub fn review_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "Review calendar import",
+            Language::Norsk => "Gå gjennom kalenderimport",
+        }
+    }
+    pub fn all_day_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "all day",
+            Language::Norsk => "hele dagen",
+        }
+    }
+    pub fn duplicate_label(lang: &Language) -> &'static str {
+        match lang {
+            Language::English => "already imported",
+            Language::Norsk => "allerede importert",
+        }
+    }
+    pub fn unmatched_events(lang: &Language, events: usize) -> String {
+        match lang {
+            Language::English => format!("{} events matched no rule", events),
+            Language::Norsk => format!("{} hendelser passet ikke med noen regel", events),
+        }
+    }
+    pub fn unsupported_events(lang: &Language, events: usize) -> String {
+        match lang {
+            Language::English => format!("{} recurring events have an unsupported rule", events),
+            Language::Norsk => format!(
+                "{} gjentatte hendelser har en regel som ikke støttes",
+                events
+            ),
+        }
+    }
+    pub fn review_help(lang: &Language, selected: usize, hours: &str) -> String {
+        match lang {
+            Language::English => {
+                let noun = if selected == 1 { "event" } else { "events" };
+                format!(
+                    "Space: include / leave out, a: all, Enter: import {} {} ({} hours), Esc: cancel",
+                    selected, noun, hours
+                )
+            }
+            Language::Norsk => {
+                let noun = if selected == 1 {
+                    "hendelse"
+                } else {
+                    "hendelser"
+                };
+                format!(
+                    "Mellomrom: ta med / utelat, a: alle, Enter: importer {} {} ({} timer), Esc: avbryt",
+                    selected, noun, hours
+                )
+            }
+        }
+    }
+    pub fn skipped_entry_days(lang: &Language, days: usize) -> String {
+        match lang {
+            Language::English => format!(", left out {} days with time entries", days),
+            Language::Norsk => format!(", utelot {} dager med tidsoppføringer", days),
+        }
+    }
 }
@@ -0,0 +1,142 @@
+use super::ics::Occurrence;
+use super::ImportRow;
+use crate::config::Config;
+use crate::data::TimekeeperData;
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// The part of an event a rule's pattern is matched against
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum RuleField {
+    #[default]
+    Summary,
+    // Any of the event's categories
+    Category,
+}
+
+// Logs calendar events that match a pattern on a timecode
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CalendarRule {
+    // Regular expression, e.g. "(?i)standup"
+    pub pattern: String,
+    pub field: RuleField,
+    // May use groups from the pattern, e.g. "ACME/$1"
+    pub timecode: String,
+    // Whether matching all-day events count as a full working day
+    pub all_day: bool,
+}
+
+// An event to be added to the timesheet, once accepted in the review
+pub struct Proposal {
+    pub date: NaiveDate,
+    // Start and end, None for all-day events
+    pub time: Option<(NaiveTime, NaiveTime)>,
+    pub summary: String,
+    pub timecode: String,
+    pub minutes: u32,
+    pub selected: bool,
+    // The day's comment already mentions the event, so it was likely imported before
+    pub duplicate: bool,
+}
+
+/* Matches each occurrence against the rules in order, the first match giving the
+ * timecode. All-day events are only proposed by rules allowing them, as a full
+ * day on working days. Returns the proposals and the number of events left out.
+ */
+pub fn propose(
+    data: &TimekeeperData,
+    conf: &Config,
+    occurrences: Vec<Occurrence>,
+) -> Result<(Vec<Proposal>, usize), String> {
+    let rules = conf
+        .calendar_rules
+        .iter()
+        .map(|r| {
+            Regex::new(&r.pattern)
+                .map(|re| (re, r))
+                .map_err(|e| format!("Invalid calendar rule {}: {}", r.pattern, e))
+        })
+        .collect::<Result<Vec<(Regex, &CalendarRule)>, String>>()?;
+
+    let mut proposals = vec![];
+    let mut left_out = 0;
+    for occurrence in occurrences {
+        let matched = rules.iter().find_map(|(re, rule)| {
+            let captures = match rule.field {
+                RuleField::Summary => re.captures(&occurrence.summary),
+                RuleField::Category => occurrence.categories.iter().find_map(|c| re.captures(c)),
+            }?;
+            let mut timecode = String::new();
+            captures.expand(&rule.timecode, &mut timecode);
+            Some((timecode, rule))
+        });
+        let (timecode, rule) = match matched {
+            Some((tc, rule)) if !tc.is_empty() && (rule.all_day || !occurrence.all_day) => {
+                (tc, rule)
+            }
+            _ => {
+                left_out += 1;
+                continue;
+            }
+        };
+
+        let date = occurrence.start.date();
+        let minutes = if rule.all_day && occurrence.all_day {
+            conf.daily_target(date)
+        } else {
+            (occurrence.end - occurrence.start).num_minutes().max(0) as u32
+        };
+        if minutes == 0 {
+            left_out += 1;
+            continue;
+        }
+        let duplicate = !occurrence.summary.is_empty()
+            && comment_of(data, date, &timecode)
+                .map(|c| c.contains(occurrence.summary.as_str()))
+                .unwrap_or(false);
+        proposals.push(Proposal {
+            date,
+            time: if occurrence.all_day {
+                None
+            } else {
+                Some((occurrence.start.time(), occurrence.end.time()))
+            },
+            summary: occurrence.summary,
+            timecode,
+            minutes,
+            selected: !duplicate,
+            duplicate,
+        });
+    }
+    Ok((proposals, left_out))
+}
+
+// The selected proposals, with the summary as comment
+pub fn accepted_rows(proposals: &[Proposal]) -> Vec<ImportRow> {
+    proposals
+        .iter()
+        .filter(|p| p.selected)
+        .map(|p| ImportRow {
+            date: p.date,
+            timecode: p.timecode.clone(),
+            seconds: p.minutes as u64 * 60,
+            comment: p.summary.clone(),
+        })
+        .collect()
+}
+
+fn comment_of<'a>(data: &'a TimekeeperData, date: NaiveDate, timecode: &str) -> Option<&'a str> {
+    let year = date.iso_week().year() as usize;
+    let week = date.iso_week().week() as u8;
+    let day = date.weekday().num_days_from_monday() as u8;
+    data.get(year)?
+        .get(week)?
+        .0
+        .iter()
+        .find(|tc| tc.timecode == timecode)?
+        .get(day)
+        .map(|d| d.comment.as_str())
+        .filter(|c| !c.is_empty())
+}
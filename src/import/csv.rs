@@ -0,0 +1,206 @@
+use super::ImportRow;
+use crate::config::Config;
+use crate::duration::parse_duration;
+use chrono::NaiveDate;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// Column layouts of the trackers we know, picked from the header when not given
+#[derive(Clone, Copy, PartialEq)]
+pub enum CsvFormat {
+    // Toggl Track's detailed report
+    Toggl,
+    // Clockify's detailed report
+    Clockify,
+    // The long format written by `timekeeper export`
+    Generic,
+}
+
+impl CsvFormat {
+    pub fn parse(s: &str) -> Option<CsvFormat> {
+        match s.to_lowercase().as_str() {
+            "toggl" => Some(CsvFormat::Toggl),
+            "clockify" => Some(CsvFormat::Clockify),
+            "csv" | "generic" => Some(CsvFormat::Generic),
+            _ => None,
+        }
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvFormat::Toggl => "toggl",
+            CsvFormat::Clockify => "clockify",
+            CsvFormat::Generic => "csv",
+        }
+    }
+
+    fn detect(headers: &[String]) -> CsvFormat {
+        let has = |h: &str| headers.iter().any(|c| c == h);
+        if has("Start date") && has("Duration") {
+            CsvFormat::Toggl
+        } else if has("Start Date") && has("Duration (h)") {
+            CsvFormat::Clockify
+        } else {
+            CsvFormat::Generic
+        }
+    }
+
+    fn mapping(&self, conf: &Config) -> CsvMapping {
+        let (date, date_format, duration, comment) = match self {
+            CsvFormat::Toggl => ("Start date", "%Y-%m-%d", "Duration", "Description"),
+            CsvFormat::Clockify => ("Start Date", "%m/%d/%Y", "Duration (h)", "Description"),
+            CsvFormat::Generic => ("date", conf.export_date_format.as_str(), "hours", "comment"),
+        };
+        let timecode = match self {
+            CsvFormat::Toggl | CsvFormat::Clockify => "Project",
+            CsvFormat::Generic => "timecode",
+        };
+        CsvMapping {
+            date: Some(date.to_string()),
+            date_format: Some(date_format.to_string()),
+            duration: Some(duration.to_string()),
+            timecode: Some(vec![timecode.to_string()]),
+            comment: Some(comment.to_string()),
+        }
+    }
+}
+
+// Columns to read, by header name. Anything left as None is taken from the format
+#[derive(Default)]
+pub struct CsvMapping {
+    pub date: Option<String>,
+    // chrono format string
+    pub date_format: Option<String>,
+    // HH:MM:SS, H:MM or decimal hours
+    pub duration: Option<String>,
+    // Values joined with the timecode separator, leaving out empty ones, e.g. client and project
+    pub timecode: Option<Vec<String>>,
+    pub comment: Option<String>,
+}
+
+/* Reads the rows of a CSV file with the given column mapping. The delimiter is
+ * guessed from the header line. Returns the format used along with the rows.
+ */
+pub fn read_csv(
+    path: &Path,
+    format: Option<CsvFormat>,
+    mapping: CsvMapping,
+    conf: &Config,
+) -> Result<(CsvFormat, Vec<ImportRow>), Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let text = text.trim_start_matches('\u{feff}');
+    let first_line = text.lines().next().unwrap_or("");
+    let delimiter = [b',', b';', b'\t']
+        .iter()
+        .copied()
+        .max_by_key(|d| first_line.matches(*d as char).count())
+        .unwrap_or(b',');
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+    let format = format.unwrap_or_else(|| CsvFormat::detect(&headers));
+    let defaults = format.mapping(conf);
+
+    let column = |name: &String| -> Result<usize, String> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("No column {} in {}", name, path.display()))
+    };
+    let date_col = column(&mapping.date.or(defaults.date).unwrap())?;
+    let date_format = mapping.date_format.or(defaults.date_format).unwrap();
+    let duration_col = column(&mapping.duration.or(defaults.duration).unwrap())?;
+    let timecode_cols = mapping
+        .timecode
+        .or(defaults.timecode)
+        .unwrap()
+        .iter()
+        .map(column)
+        .collect::<Result<Vec<usize>, String>>()?;
+    // A missing comment column is only an error when asked for by name
+    let comment_col = match mapping.comment {
+        Some(c) => Some(column(&c)?),
+        None => defaults.comment.and_then(|c| column(&c).ok()),
+    };
+
+    let mut rows = vec![];
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        // Counting the header as line 1
+        let line = i + 2;
+        let field = |col: usize| record.get(col).unwrap_or("").trim();
+        if record.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        let date = NaiveDate::parse_from_str(field(date_col), &date_format).map_err(|_| {
+            format!(
+                "Line {}: invalid date {}, expected {}",
+                line,
+                field(date_col),
+                date_format
+            )
+        })?;
+        let seconds = parse_seconds(field(duration_col))
+            .ok_or_else(|| format!("Line {}: invalid duration {}", line, field(duration_col)))?;
+        let timecode = timecode_cols
+            .iter()
+            .map(|c| field(*c))
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<&str>>()
+            .join(&conf.timecode_separator);
+        if timecode.is_empty() {
+            return Err(format!("Line {}: no timecode", line).into());
+        }
+        rows.push(ImportRow {
+            date,
+            timecode,
+            seconds,
+            comment: comment_col.map(field).unwrap_or("").to_string(),
+        });
+    }
+    Ok((format, rows))
+}
+
+// "1:30:15", or anything parse_duration takes: "1:30", "1.5"
+fn parse_seconds(s: &str) -> Option<u64> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if let [h, m, sec] = parts.as_slice() {
+        let (h, m, sec): (u64, u64, u64) = (h.parse().ok()?, m.parse().ok()?, sec.parse().ok()?);
+        if m >= 60 || sec >= 60 {
+            return None;
+        }
+        return h.checked_mul(3600)?.checked_add(m * 60 + sec);
+    }
+    parse_duration(s).map(|m| m as u64 * 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_seconds() {
+        let cases = [
+            ("1:30:15", Some(5415)),
+            ("0:00:59", Some(59)),
+            ("1:30", Some(5400)),
+            ("1.5", Some(5400)),
+            ("0:60:00", None),
+            ("0:00:60", None),
+            ("1:x:00", None),
+            ("99999999999999999:00:00", None),
+            ("", None),
+        ];
+        for (input, seconds) in cases {
+            assert_eq!(parse_seconds(input), seconds, "{}", input);
+        }
+    }
+}
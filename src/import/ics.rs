@@ -0,0 +1,588 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// Recurrences in the range are cut off after this many, in case of a runaway rule
+const MAX_OCCURRENCES: usize = 5000;
+
+// An event in the calendar, taking place once or by its recurrence rule
+struct Event {
+    uid: String,
+    summary: String,
+    categories: Vec<String>,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    all_day: bool,
+    rrule: Option<RRule>,
+    // The event recurs by a rule that isn't supported, so its occurrences are unknown
+    unsupported_rule: bool,
+    exdates: Vec<NaiveDateTime>,
+    // Set on an event that replaces one occurrence of a recurring event
+    recurrence_id: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+struct RRule {
+    freq: Freq,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDateTime>,
+    // Weekdays, with an ordinal within the month for monthly rules, e.g. -1FR
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_month_day: Vec<i32>,
+}
+
+// A single time an event takes place
+pub struct Occurrence {
+    pub summary: String,
+    pub categories: Vec<String>,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub all_day: bool,
+}
+
+/* Every occurrence of the events in an iCalendar file that starts between
+ * `from` and `to`, inclusive, in start order. Multi-day all-day events give an
+ * occurrence per day. Cancelled events are left out, and so are events recurring
+ * by a rule that isn't supported; the number of those that may fall in the
+ * range is returned with the occurrences.
+ */
+pub fn read_ics(
+    path: &Path,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<(Vec<Occurrence>, usize), Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(occurrences(&text, from, to)?)
+}
+
+fn occurrences(
+    text: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<(Vec<Occurrence>, usize), String> {
+    let events = parse(text)?;
+
+    // Occurrences moved or changed are replaced by their own event
+    let overridden: HashSet<(String, NaiveDateTime)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.clone(), e.recurrence_id?)))
+        .collect();
+
+    let mut occurrences = vec![];
+    let mut unsupported = 0;
+    for event in events.iter() {
+        if event.unsupported_rule {
+            if event.start.date() <= to {
+                unsupported += 1;
+            }
+            continue;
+        }
+        let length = event.end - event.start;
+        // All-day events starting before the range may last into it
+        let earliest = from.and_hms(0, 0, 0) - length.max(Duration::zero());
+        for start in starts(event, earliest, to) {
+            if event.recurrence_id.is_none() && overridden.contains(&(event.uid.clone(), start)) {
+                continue;
+            }
+            if event.all_day {
+                let days = length.num_days().max(1);
+                for offset in 0..days {
+                    let day = start + Duration::days(offset);
+                    if day.date() >= from && day.date() <= to {
+                        occurrences.push(Occurrence {
+                            summary: event.summary.clone(),
+                            categories: event.categories.clone(),
+                            start: day,
+                            end: day + Duration::days(1),
+                            all_day: true,
+                        });
+                    }
+                }
+            } else if start.date() >= from && start.date() <= to {
+                occurrences.push(Occurrence {
+                    summary: event.summary.clone(),
+                    categories: event.categories.clone(),
+                    start,
+                    end: start + length,
+                    all_day: false,
+                });
+            }
+        }
+    }
+    occurrences.sort_by_key(|o| o.start);
+    Ok((occurrences, unsupported))
+}
+
+/* Start times of an event from `from` up to the end of `to`, leaving out its
+ * exception dates. Expansion begins at the first period that reaches `from`,
+ * unless the rule has a COUNT, which includes the occurrences before it.
+ */
+fn starts(event: &Event, from: NaiveDateTime, to: NaiveDate) -> Vec<NaiveDateTime> {
+    let rule = match &event.rrule {
+        Some(r) => r,
+        None => return vec![event.start],
+    };
+    let end_of_range = to.and_hms(23, 59, 59);
+    let last = rule
+        .until
+        .map(|u| u.min(end_of_range))
+        .unwrap_or(end_of_range);
+    let interval = rule.interval.max(1) as i64;
+    let time = event.start.time();
+    let first = event.start.date();
+    let counted_out = |counted: usize| rule.count.map(|c| counted >= c).unwrap_or(false);
+
+    let mut starts = vec![];
+    // Occurrences so far, including those before `from`
+    let mut counted = 0;
+    let mut period = match rule.count {
+        Some(_) => 0,
+        None => first_period(rule.freq, first, from.date(), interval),
+    };
+    loop {
+        // First day of the period, and the days in it that match, in order
+        let (period_start, days): (NaiveDate, Vec<NaiveDate>) = match rule.freq {
+            Freq::Daily => {
+                let day = first + Duration::days(period * interval);
+                (day, vec![day])
+            }
+            Freq::Weekly => {
+                let monday = first - Duration::days(first.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(period * interval);
+                let mut weekdays: Vec<Weekday> = rule.by_day.iter().map(|(_, d)| *d).collect();
+                if weekdays.is_empty() {
+                    weekdays.push(first.weekday());
+                }
+                weekdays.sort_by_key(|d| d.num_days_from_monday());
+                let days = weekdays
+                    .iter()
+                    .map(|d| monday + Duration::days(d.num_days_from_monday() as i64))
+                    .collect();
+                (monday, days)
+            }
+            Freq::Monthly => {
+                let month = first.year() as i64 * 12 + first.month0() as i64 + period * interval;
+                let (year, month) = (month.div_euclid(12) as i32, month.rem_euclid(12) as u32 + 1);
+                let mut days: Vec<NaiveDate> = if !rule.by_day.is_empty() {
+                    rule.by_day
+                        .iter()
+                        .filter_map(|(n, d)| nth_weekday(year, month, n.unwrap_or(1), *d))
+                        .collect()
+                } else if !rule.by_month_day.is_empty() {
+                    rule.by_month_day
+                        .iter()
+                        .filter_map(|d| month_day(year, month, *d))
+                        .collect()
+                } else {
+                    month_day(year, month, first.day() as i32)
+                        .into_iter()
+                        .collect()
+                };
+                days.sort();
+                (NaiveDate::from_ymd(year, month, 1), days)
+            }
+            Freq::Yearly => {
+                let year = first.year() + (period * interval) as i32;
+                let days = NaiveDate::from_ymd_opt(year, first.month(), first.day())
+                    .into_iter()
+                    .collect();
+                (NaiveDate::from_ymd(year, 1, 1), days)
+            }
+        };
+        period += 1;
+        if period_start > last.date() {
+            break;
+        }
+
+        // Periods without a matching day, like February for the 30th, are skipped
+        for day in days {
+            let start = day.and_time(time);
+            if counted_out(counted) || starts.len() >= MAX_OCCURRENCES {
+                break;
+            }
+            if start >= event.start && start <= last {
+                counted += 1;
+                if start >= from {
+                    starts.push(start);
+                }
+            }
+        }
+        if counted_out(counted) || starts.len() >= MAX_OCCURRENCES {
+            break;
+        }
+    }
+    starts.retain(|s| !event.exdates.contains(s));
+    starts
+}
+
+// The first period of a rule starting on `first` that can have a day on or after `from`
+fn first_period(freq: Freq, first: NaiveDate, from: NaiveDate, interval: i64) -> i64 {
+    let periods = match freq {
+        Freq::Daily => (from - first).num_days(),
+        Freq::Weekly => {
+            let monday = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+            (from - monday).num_days().div_euclid(7)
+        }
+        Freq::Monthly => {
+            (from.year() - first.year()) as i64 * 12 + from.month0() as i64 - first.month0() as i64
+        }
+        Freq::Yearly => (from.year() - first.year()) as i64,
+    };
+    periods.div_euclid(interval).max(0)
+}
+
+// The nth weekday of a month, counting from the end when negative
+fn nth_weekday(year: i32, month: u32, n: i32, weekday: Weekday) -> Option<NaiveDate> {
+    if n > 0 {
+        NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8)
+    } else {
+        let (_, last) = month_bounds(year, month);
+        let back = (7 + last.weekday().num_days_from_monday() as i64
+            - weekday.num_days_from_monday() as i64)
+            % 7;
+        let date = last - Duration::days(back + 7 * (-n as i64 - 1));
+        Some(date).filter(|d| d.month() == month)
+    }
+}
+
+// A day of the month, counting from the end when negative
+fn month_day(year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+    if day > 0 {
+        NaiveDate::from_ymd_opt(year, month, day as u32)
+    } else {
+        let (_, last) = month_bounds(year, month);
+        Some(last + Duration::days(day as i64 + 1)).filter(|d| d.month() == month)
+    }
+}
+
+fn month_bounds(year: i32, month: u32) -> (NaiveDate, NaiveDate) {
+    let first = NaiveDate::from_ymd(year, month, 1);
+    let next = if month == 12 {
+        NaiveDate::from_ymd(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(year, month + 1, 1)
+    };
+    (first, next - Duration::days(1))
+}
+
+fn parse(text: &str) -> Result<Vec<Event>, String> {
+    // Long lines are folded onto lines starting with a space or tab
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match line.strip_prefix(|c| c == ' ' || c == '\t') {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.trim_end_matches('\r').to_string()),
+        }
+    }
+
+    let mut events = vec![];
+    let mut current: Option<Vec<(String, String, String)>> = None;
+    // Alarms nested in an event have properties of their own, which are skipped
+    let mut nested = 0;
+    for line in lines {
+        if current.is_some() && line.starts_with("BEGIN:") {
+            nested += 1;
+        } else if nested > 0 {
+            if line.starts_with("END:") {
+                nested -= 1;
+            }
+        } else if line == "BEGIN:VEVENT" {
+            current = Some(vec![]);
+        } else if line == "END:VEVENT" {
+            if let Some(props) = current.take() {
+                if let Some(event) = event(&props)? {
+                    events.push(event);
+                }
+            }
+        } else if let Some(props) = current.as_mut() {
+            // NAME;PARAM=VALUE:value, where only the value may contain a colon
+            if let Some((name, value)) = line.split_once(':') {
+                let (name, params) = name.split_once(';').unwrap_or((name, ""));
+                props.push((
+                    name.to_uppercase(),
+                    params.to_uppercase(),
+                    value.to_string(),
+                ));
+            }
+        }
+    }
+    Ok(events)
+}
+
+// None for cancelled events
+fn event(props: &[(String, String, String)]) -> Result<Option<Event>, String> {
+    let get = |name: &str| props.iter().find(|(n, _, _)| n == name);
+    if get("STATUS")
+        .map(|(_, _, v)| v == "CANCELLED")
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+    let (_, _, start) = get("DTSTART").ok_or("Event without DTSTART")?;
+    let (start, all_day) = parse_time(start)?;
+    let end = match (get("DTEND"), get("DURATION")) {
+        (Some((_, _, v)), _) => parse_time(v)?.0,
+        (None, Some((_, _, v))) => start + parse_duration(v)?,
+        (None, None) if all_day => start + Duration::days(1),
+        (None, None) => start,
+    };
+    let exdates = props
+        .iter()
+        .filter(|(n, _, _)| n == "EXDATE")
+        .flat_map(|(_, _, v)| v.split(',').map(parse_time))
+        .map(|r| r.map(|(t, _)| t))
+        .collect::<Result<Vec<NaiveDateTime>, String>>()?;
+    // Unsupported rules leave the event out, rather than the whole file
+    let (rrule, unsupported_rule) = match get("RRULE").map(|(_, _, v)| parse_rrule(v)) {
+        Some(Ok(rule)) => (Some(rule), false),
+        Some(Err(_)) => (None, true),
+        None => (None, false),
+    };
+    Ok(Some(Event {
+        uid: get("UID").map(|(_, _, v)| v.clone()).unwrap_or_default(),
+        summary: get("SUMMARY")
+            .map(|(_, _, v)| unescape(v))
+            .unwrap_or_default(),
+        categories: props
+            .iter()
+            .filter(|(n, _, _)| n == "CATEGORIES")
+            .flat_map(|(_, _, v)| v.split(',').map(|c| unescape(c.trim())))
+            .collect(),
+        start,
+        end,
+        all_day,
+        rrule,
+        unsupported_rule,
+        exdates,
+        recurrence_id: get("RECURRENCE-ID")
+            .map(|(_, _, v)| parse_time(v).map(|(t, _)| t))
+            .transpose()?,
+    }))
+}
+
+/* A DATE or DATE-TIME value, and whether it was a date. UTC times are shown in
+ * local time. Times with a TZID are taken as local time, since there is no time
+ * zone database to convert them with.
+ */
+fn parse_time(value: &str) -> Result<(NaiveDateTime, bool), String> {
+    let invalid = || format!("Invalid date {}", value);
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| invalid())?;
+        return Ok((date.and_hms(0, 0, 0), true));
+    }
+    let utc = value.ends_with('Z');
+    let time = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+        .map_err(|_| invalid())?;
+    if utc {
+        Ok((Local.from_utc_datetime(&time).naive_local(), false))
+    } else {
+        Ok((time, false))
+    }
+}
+
+// "PT1H30M", "P1D", "P1W"
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid duration {}", value);
+    let rest = value.strip_prefix('P').ok_or_else(invalid)?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => (),
+            unit => {
+                let n: i64 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                total = total
+                    + match unit {
+                        'W' => Duration::weeks(n),
+                        'D' => Duration::days(n),
+                        'H' => Duration::hours(n),
+                        'M' => Duration::minutes(n),
+                        'S' => Duration::seconds(n),
+                        _ => return Err(invalid()),
+                    };
+            }
+        }
+    }
+    Ok(total)
+}
+
+fn parse_rrule(value: &str) -> Result<RRule, String> {
+    let invalid = || format!("Unsupported RRULE {}", value);
+    let mut rule = RRule {
+        freq: Freq::Daily,
+        interval: 1,
+        count: None,
+        until: None,
+        by_day: vec![],
+        by_month_day: vec![],
+    };
+    let mut has_freq = false;
+    for part in value.split(';') {
+        let (key, v) = part.split_once('=').ok_or_else(invalid)?;
+        match key {
+            "FREQ" => {
+                has_freq = true;
+                rule.freq = match v {
+                    "DAILY" => Freq::Daily,
+                    "WEEKLY" => Freq::Weekly,
+                    "MONTHLY" => Freq::Monthly,
+                    "YEARLY" => Freq::Yearly,
+                    _ => return Err(invalid()),
+                }
+            }
+            "INTERVAL" => rule.interval = v.parse().map_err(|_| invalid())?,
+            "COUNT" => rule.count = Some(v.parse().map_err(|_| invalid())?),
+            "UNTIL" => {
+                let (until, date) = parse_time(v)?;
+                // A date includes the whole day
+                rule.until = Some(if date {
+                    until + Duration::days(1) - Duration::seconds(1)
+                } else {
+                    until
+                });
+            }
+            "BYDAY" => {
+                for day in v.split(',') {
+                    let split = day.len().saturating_sub(2);
+                    let (n, d) = day.split_at(split);
+                    let n = if n.is_empty() {
+                        None
+                    } else {
+                        Some(n.trim_start_matches('+').parse().map_err(|_| invalid())?)
+                    };
+                    rule.by_day.push((n, parse_weekday(d).ok_or_else(invalid)?));
+                }
+            }
+            "BYMONTHDAY" => {
+                for day in v.split(',') {
+                    rule.by_month_day.push(day.parse().map_err(|_| invalid())?);
+                }
+            }
+            // Only affects which weekday a week starts on, and Monday is assumed
+            "WKST" => (),
+            _ => return Err(invalid()),
+        }
+    }
+    if !has_freq {
+        return Err(invalid());
+    }
+    Ok(rule)
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(events: &[&str]) -> String {
+        let mut text = String::from("BEGIN:VCALENDAR\r\n");
+        for props in events {
+            text.push_str("BEGIN:VEVENT\r\n");
+            text.push_str(props);
+            text.push_str("END:VEVENT\r\n");
+        }
+        text.push_str("END:VCALENDAR\r\n");
+        text
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(y, m, d)
+    }
+
+    fn start_dates(text: &str, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let (occurrences, _) = occurrences(text, from, to).unwrap();
+        occurrences.iter().map(|o| o.start.date()).collect()
+    }
+
+    #[test]
+    fn events_with_unsupported_rules_are_left_out() {
+        let text = calendar(&[
+            "UID:1\r\nSUMMARY:Standup\r\nDTSTART:20261012T090000\r\nDTEND:20261012T091500\r\n",
+            "UID:2\r\nSUMMARY:Clocks change\r\nDTSTART:20000326T020000\r\n\
+             RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n",
+            "UID:3\r\nSUMMARY:Later\r\nDTSTART:20300101T090000\r\nRRULE:FREQ=YEARLY;BYWEEKNO=1\r\n",
+        ]);
+        let (occurrences, unsupported) =
+            occurrences(&text, date(2026, 10, 12), date(2026, 10, 18)).unwrap();
+        assert_eq!(occurrences.len(), 1);
+        assert_eq!(occurrences[0].summary, "Standup");
+        // The one starting after the range can't have occurrences in it
+        assert_eq!(unsupported, 1);
+    }
+
+    #[test]
+    fn old_recurring_events_reach_the_range() {
+        let daily = calendar(&["UID:1\r\nDTSTART:20120102T090000\r\nRRULE:FREQ=DAILY\r\n"]);
+        let (from, to) = (date(2026, 10, 12), date(2026, 10, 18));
+        assert_eq!(start_dates(&daily, from, to).len(), 7);
+
+        // Every other week since 2010, which puts the first in the range on the 19th
+        let biweekly = calendar(&[
+            "UID:1\r\nDTSTART:20100104T090000\r\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE\r\n",
+        ]);
+        assert_eq!(
+            start_dates(&biweekly, from, date(2026, 10, 25)),
+            [date(2026, 10, 19), date(2026, 10, 21)]
+        );
+
+        let monthly =
+            calendar(&["UID:1\r\nDTSTART:20050131T090000\r\nRRULE:FREQ=MONTHLY;BYMONTHDAY=-1\r\n"]);
+        assert_eq!(
+            start_dates(&monthly, date(2026, 1, 1), date(2026, 3, 31)),
+            [date(2026, 1, 31), date(2026, 2, 28), date(2026, 3, 31)]
+        );
+    }
+
+    #[test]
+    fn count_includes_occurrences_before_the_range() {
+        let text = calendar(&["UID:1\r\nDTSTART:20261010T090000\r\nRRULE:FREQ=DAILY;COUNT=3\r\n"]);
+        assert_eq!(
+            start_dates(&text, date(2026, 10, 11), date(2026, 10, 18)),
+            [date(2026, 10, 11), date(2026, 10, 12)]
+        );
+    }
+
+    #[test]
+    fn all_day_events_started_before_the_range_are_included() {
+        let text = calendar(&[
+            "UID:1\r\nDTSTART;VALUE=DATE:20261003\r\nDTEND;VALUE=DATE:20261006\r\n\
+             RRULE:FREQ=WEEKLY\r\n",
+        ]);
+        assert_eq!(
+            start_dates(&text, date(2026, 10, 12), date(2026, 10, 13)),
+            [date(2026, 10, 12)]
+        );
+    }
+}
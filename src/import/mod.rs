@@ -0,0 +1,248 @@
+mod calendar;
+mod csv;
+mod ics;
+
+use crate::data::{Day, DayRecord, TimekeeperData};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+pub use self::calendar::{accepted_rows, propose, CalendarRule, Proposal};
+pub use self::csv::{read_csv, CsvFormat, CsvMapping};
+pub use self::ics::read_ics;
+
+// A stretch of work read from another tracker
+pub struct ImportRow {
+    pub date: NaiveDate,
+    pub timecode: String,
+    pub seconds: u64,
+    pub comment: String,
+}
+
+// What to do with a day that already has hours or a comment in the timesheet
+#[derive(Clone, Copy, PartialEq)]
+pub enum OnConflict {
+    Skip,
+    Replace,
+    // Add the imported hours and comment to what's there
+    Add,
+}
+impl OnConflict {
+    pub fn parse(s: &str) -> Option<OnConflict> {
+        match s {
+            "skip" => Some(OnConflict::Skip),
+            "replace" => Some(OnConflict::Replace),
+            "add" => Some(OnConflict::Add),
+            _ => None,
+        }
+    }
+}
+
+// An imported day that lands on one already in the timesheet
+#[derive(Serialize)]
+pub struct Conflict {
+    pub date: NaiveDate,
+    pub timecode: String,
+    pub existing: u32,
+    pub imported: u32,
+    // Left as it was in the timesheet
+    pub skipped: bool,
+}
+
+#[derive(Default)]
+pub struct ImportSummary {
+    // Timecode/day cells written
+    pub days: usize,
+    pub minutes: u32,
+    pub conflicts: Vec<Conflict>,
+    pub weeks: HashSet<(usize, u8)>,
+    pub timecodes: Vec<String>,
+}
+
+/* Sums the rows per timecode and day, and merges them into `data`. Durations
+ * are summed before rounding to minutes, so many short rows don't drift.
+ * Descriptions are joined into the day's comment, each only once.
+ */
+pub fn merge(
+    data: &mut TimekeeperData,
+    rows: Vec<ImportRow>,
+    on_conflict: OnConflict,
+) -> ImportSummary {
+    // Ordered by date, so the summary lists days as they fall
+    let mut days: BTreeMap<(NaiveDate, String), (u64, Vec<String>)> = BTreeMap::new();
+    for row in rows {
+        let day = days.entry((row.date, row.timecode)).or_default();
+        day.0 += row.seconds;
+        let comment = row.comment.trim();
+        if !comment.is_empty() && !day.1.iter().any(|c| c == comment) {
+            day.1.push(comment.to_string());
+        }
+    }
+
+    let days = days
+        .into_iter()
+        .map(|((date, timecode), (seconds, comments))| DayRecord {
+            date,
+            timecode,
+            minutes: ((seconds + 30) / 60) as u32,
+            comment: comments.join("; "),
+            entries: vec![],
+        })
+        .collect();
+    merge_days(data, days, on_conflict)
+}
+
+/* Merges whole days into `data`, along with their time entries. Adding to a day
+ * is only possible when both or neither have time entries, since the hours of a
+ * day with entries are their total.
+ */
+pub fn merge_days(
+    data: &mut TimekeeperData,
+    days: Vec<DayRecord>,
+    on_conflict: OnConflict,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    for record in days {
+        let DayRecord {
+            date,
+            timecode,
+            minutes,
+            comment,
+            entries,
+        } = record;
+        if minutes == 0 && comment.is_empty() && entries.is_empty() {
+            continue;
+        }
+        let year = date.iso_week().year() as usize;
+        let week = date.iso_week().week() as u8;
+        let day = date.weekday().num_days_from_monday() as u8;
+
+        let mut value = Day::new(minutes);
+        value.comment = comment;
+        value.entries = entries;
+        let existing = data
+            .get(year)
+            .and_then(|y| y.get(week))
+            .and_then(|w| w.0.iter().find(|tc| tc.timecode == timecode))
+            .and_then(|tc| tc.get(day))
+            .filter(|d| d.minutes > 0 || !d.comment.is_empty() || !d.entries.is_empty())
+            .cloned();
+        if let Some(existing) = existing {
+            let skipped = on_conflict == OnConflict::Skip
+                || (on_conflict == OnConflict::Add
+                    && existing.entries.is_empty() != value.entries.is_empty());
+            summary.conflicts.push(Conflict {
+                date,
+                timecode: timecode.clone(),
+                existing: existing.minutes,
+                imported: minutes,
+                skipped,
+            });
+            if skipped {
+                continue;
+            }
+            if on_conflict == OnConflict::Add {
+                let mut merged = existing;
+                merged.merge(value);
+                value = merged;
+            }
+        }
+        data.set_day(year, week, &timecode, day, value);
+        summary.days += 1;
+        summary.minutes += minutes;
+        summary.weeks.insert((year, week));
+        if !summary.timecodes.contains(&timecode) {
+            summary.timecodes.push(timecode);
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: NaiveDate, timecode: &str, seconds: u64, comment: &str) -> ImportRow {
+        ImportRow {
+            date,
+            timecode: timecode.to_string(),
+            seconds,
+            comment: comment.to_string(),
+        }
+    }
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd(2026, 10, 12)
+    }
+
+    // Monday of week 42 in 2026 with an hour logged on ACME
+    fn logged() -> TimekeeperData {
+        let mut data = TimekeeperData::default();
+        let mut day = Day::new(60);
+        day.comment = String::from("planning");
+        data.set_day(2026, 42, "ACME", 0, day);
+        data
+    }
+
+    fn acme_monday(data: &TimekeeperData) -> (u32, String) {
+        let week = data.get(2026).unwrap().get(42).unwrap();
+        let day = week.0.iter().find(|tc| tc.timecode == "ACME").unwrap();
+        let day = day.get(0).unwrap();
+        (day.minutes, day.comment.clone())
+    }
+
+    fn rows() -> Vec<ImportRow> {
+        let tuesday = monday().succ();
+        vec![
+            row(tuesday, "Support", 1800, ""),
+            row(monday(), "ACME", 2700, "review"),
+            row(monday(), "ACME", 1815, "review"),
+            row(monday(), "ACME", 0, "deploy"),
+        ]
+    }
+
+    #[test]
+    fn rows_are_summed_per_timecode_and_day() {
+        let mut data = TimekeeperData::default();
+        let summary = merge(&mut data, rows(), OnConflict::Skip);
+        // 45:00 and 30:15 are summed before rounding
+        assert_eq!(acme_monday(&data), (75, String::from("review; deploy")));
+        assert_eq!(summary.days, 2);
+        assert_eq!(summary.minutes, 105);
+        assert_eq!(summary.timecodes, ["ACME", "Support"]);
+        assert!(summary.conflicts.is_empty());
+    }
+
+    #[test]
+    fn skip_keeps_the_day_in_the_timesheet() {
+        let mut data = logged();
+        let summary = merge(&mut data, rows(), OnConflict::Skip);
+        assert_eq!(acme_monday(&data), (60, String::from("planning")));
+        assert_eq!(summary.days, 1);
+        assert_eq!(summary.conflicts.len(), 1);
+        assert!(summary.conflicts[0].skipped);
+        assert_eq!(summary.conflicts[0].existing, 60);
+        assert_eq!(summary.conflicts[0].imported, 75);
+    }
+
+    #[test]
+    fn replace_overwrites_the_day() {
+        let mut data = logged();
+        let summary = merge(&mut data, rows(), OnConflict::Replace);
+        assert_eq!(acme_monday(&data), (75, String::from("review; deploy")));
+        assert_eq!(summary.days, 2);
+        assert!(!summary.conflicts[0].skipped);
+    }
+
+    #[test]
+    fn add_sums_hours_and_joins_comments() {
+        let mut data = logged();
+        let summary = merge(&mut data, rows(), OnConflict::Add);
+        assert_eq!(
+            acme_monday(&data),
+            (135, String::from("planning; review; deploy"))
+        );
+        assert_eq!(summary.days, 2);
+        assert!(!summary.conflicts[0].skipped);
+    }
+}
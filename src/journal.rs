@@ -0,0 +1,170 @@
+use crate::data::{Absence, Day, RunningTimer, Timecode, TimekeeperData, Week};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/* Append-only log of edits made since the last save, one JSON object per line.
+ * Entries hold the resulting value rather than the change, so replaying them
+ * on top of a file that already contains some of them is harmless.
+ */
+#[derive(Serialize, Deserialize)]
+pub enum JournalEntry {
+    SetDay {
+        year: usize,
+        week: u8,
+        timecode: String,
+        day: u8,
+        value: Day,
+    },
+    ClearDay {
+        year: usize,
+        week: u8,
+        timecode: String,
+        day: u8,
+    },
+    AddTimecode {
+        year: usize,
+        week: u8,
+        timecode: String,
+    },
+    RemoveTimecode {
+        year: usize,
+        week: u8,
+        timecode: String,
+    },
+    // Replaces a whole week, for edits that span many cells
+    SetWeek {
+        year: usize,
+        week: u8,
+        value: Week,
+    },
+    SetTimer(Option<RunningTimer>),
+    SetAbsence {
+        date: NaiveDate,
+        value: Option<Absence>,
+    },
+}
+
+impl JournalEntry {
+    // (year, week) the entry applies to, if any
+    pub fn week(&self) -> Option<(usize, u8)> {
+        match self {
+            JournalEntry::SetDay { year, week, .. }
+            | JournalEntry::ClearDay { year, week, .. }
+            | JournalEntry::AddTimecode { year, week, .. }
+            | JournalEntry::RemoveTimecode { year, week, .. }
+            | JournalEntry::SetWeek { year, week, .. } => Some((*year, *week)),
+            JournalEntry::SetTimer(_) | JournalEntry::SetAbsence { .. } => None,
+        }
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Journal {
+    // The journal lives next to the data file as <data file>.journal
+    pub fn new(data_path: &Path) -> Journal {
+        let mut path = data_path.as_os_str().to_owned();
+        path.push(".journal");
+        Journal {
+            path: PathBuf::from(path),
+            file: None,
+        }
+    }
+
+    /* Reads all complete entries. A line cut short by a crash mid-append is
+     * skipped rather than failing the whole recovery.
+     */
+    pub fn read(&self) -> Vec<JournalEntry> {
+        match fs::read_to_string(&self.path) {
+            Ok(contents) => contents
+                .lines()
+                .filter_map(|l| serde_json::from_str(l).ok())
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    pub fn append(&mut self, entry: &JournalEntry) -> io::Result<()> {
+        if self.file.is_none() {
+            self.file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?,
+            );
+        }
+        let file = self.file.as_mut().unwrap();
+        let mut line = serde_json::to_string(entry).expect("ERR: Unable to convert data to JSON!");
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.sync_data()
+    }
+
+    // Called once the edits are safely in the data file
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.file = None;
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Applies the entries to `data`, returning the weeks that were touched
+pub fn replay(data: &mut TimekeeperData, entries: Vec<JournalEntry>) -> HashSet<(usize, u8)> {
+    let mut weeks = HashSet::new();
+    for entry in entries.iter() {
+        weeks.extend(entry.week());
+        apply(data, entry);
+    }
+    weeks
+}
+
+pub fn apply(data: &mut TimekeeperData, entry: &JournalEntry) {
+    match entry {
+        JournalEntry::SetDay {
+            year,
+            week,
+            timecode,
+            day,
+            value,
+        } => data.set_day(*year, *week, timecode, *day, value.clone()),
+        JournalEntry::ClearDay {
+            year,
+            week,
+            timecode,
+            day,
+        } => data.clear_day(*year, *week, timecode, *day),
+        JournalEntry::AddTimecode {
+            year,
+            week,
+            timecode,
+        } => data
+            .week_entry(*year, *week)
+            .add_timecodes(vec![Timecode::from_string(timecode.clone())]),
+        JournalEntry::RemoveTimecode {
+            year,
+            week,
+            timecode,
+        } => data.week_entry(*year, *week).remove_timecode(timecode),
+        JournalEntry::SetWeek { year, week, value } => {
+            *data.week_entry(*year, *week) = value.clone()
+        }
+        JournalEntry::SetTimer(timer) => data.timer = timer.clone(),
+        JournalEntry::SetAbsence { date, value } => match value {
+            Some(a) => {
+                data.absences.insert(*date, a.clone());
+            }
+            None => {
+                data.absences.remove(date);
+            }
+        },
+    }
+}
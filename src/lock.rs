@@ -0,0 +1,67 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/* Advisory lock marking the data file as in use by a running instance.
+ * The lock file holds the owner's pid and start time so the warning shown to
+ * other instances can tell the user who holds it. Removed again on drop.
+ */
+pub struct DataLock {
+    path: PathBuf,
+    held: bool,
+}
+
+impl DataLock {
+    pub fn new(data_path: &Path) -> DataLock {
+        let mut path = data_path.as_os_str().to_owned();
+        path.push(".lock");
+        DataLock {
+            path: PathBuf::from(path),
+            held: false,
+        }
+    }
+
+    // Returns a description of the current owner if another instance holds the lock
+    pub fn try_acquire(&mut self) -> io::Result<Option<String>> {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&self.path)
+        {
+            Ok(mut f) => {
+                f.write_all(owner_line().as_bytes())?;
+                self.held = true;
+                Ok(None)
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let owner = fs::read_to_string(&self.path).unwrap_or_default();
+                Ok(Some(owner.trim().to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Takes over a lock held by someone else, e.g. one left behind by a crash
+    pub fn force_acquire(&mut self) -> io::Result<()> {
+        fs::write(&self.path, owner_line())?;
+        self.held = true;
+        Ok(())
+    }
+}
+
+impl Drop for DataLock {
+    fn drop(&mut self) {
+        if self.held {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn owner_line() -> String {
+    format!(
+        "pid {} since {}\n",
+        process::id(),
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    )
+}
@@ -1,9 +1,24 @@
 mod app;
+mod cli;
+mod clock;
 mod config;
 mod data;
+mod duration;
 mod event;
+mod export;
+mod flex;
 mod handlers;
+mod hierarchy;
+mod history;
+mod holidays;
 mod i18n;
+mod import;
+mod journal;
+mod lock;
+mod persist;
+mod report;
+mod schema;
+mod storage;
 mod ui;
 use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::execute;
@@ -13,17 +28,50 @@ use crossterm::terminal::{
 use std::env;
 use std::error::Error;
 use std::io::{self, stdout};
+use std::path::{Path, PathBuf};
 use tui::backend::CrosstermBackend;
 use tui::Terminal;
 
 use crate::app::State;
+use crate::lock::DataLock;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let path = format!(
-        "{}/.config/timekeeper/timer.json",
+    let dir = PathBuf::from(format!(
+        "{}/.config/timekeeper",
         env::var("HOME").expect("ERR: HOME variable not set!"),
-    );
-    let mut app = app::App::new(path)?;
+    ));
+    let mut conf = match config::Config::new() {
+        Ok(conf) => conf,
+        Err(e) => {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Subcommands run without entering the TUI
+    let args: Vec<String> = env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(Some(command)) => {
+            if let Err(e) = cli::run(command, &mut conf, &dir) {
+                eprintln!("ERR: {}", e);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Ok(None) => (),
+        Err(e) => {
+            eprintln!("ERR: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    let mut app = match open_app(conf, &dir) {
+        Ok(app) => app,
+        Err(e) => {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -40,12 +88,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     terminal.draw(|f| ui::draw_main_layout(f, &app))?;
     loop {
         if app.get_state() == &State::Quit {
-            app.write();
+            if app.write() {
+                break;
+            }
+            // Saving failed; stay open so nothing is lost
+            app.cancel_quit();
+            terminal.draw(|f| ui::draw_main_layout(f, &app))?;
+        }
+        if app.get_state() == &State::ForceQuit {
             break;
         }
         match events.next()? {
             event::Event::Input(key) => {
-                // Force quit
+                // Force quit. Unsaved edits stay in the journal and are offered on next launch
                 if key == event::Key::Ctrl('c') {
                     break;
                     // Quit and save
@@ -54,6 +109,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 terminal.draw(|f| ui::draw_main_layout(f, &app))?;
             }
             event::Event::Tick => {
+                app.on_tick();
                 tick += 1;
                 // Draw every so often in case of resize
                 if tick % 2 == 0 {
@@ -68,6 +124,19 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/* Takes the lock before opening the storage, since opening may upgrade the
+ * file. If another instance has it, the data is only read.
+ */
+fn open_app(conf: config::Config, dir: &Path) -> Result<app::App, Box<dyn Error>> {
+    let mut lock = DataLock::new(&storage::data_path(conf.storage, dir));
+    let lock_owner = lock.try_acquire()?;
+    let storage = match lock_owner {
+        None => storage::open(conf.storage, dir)?,
+        Some(_) => storage::open_read_only(conf.storage, dir)?,
+    };
+    app::App::new(conf, storage, lock, lock_owner)
+}
+
 fn close_application() -> std::io::Result<()> {
     disable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -0,0 +1,129 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const BACKUP_DIR: &str = "backups";
+
+/* Writes `contents` to a temporary file next to `path`, flushes it to disk and
+ * renames it over `path`. A crash at any point leaves either the old or the
+ * new file in place, never a truncated one.
+ */
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+
+    let mut tmp = File::create(&tmp_path)?;
+    tmp.write_all(contents)?;
+    tmp.sync_all()?;
+    drop(tmp);
+
+    fs::rename(&tmp_path, path)?;
+    sync_parent(path);
+    Ok(())
+}
+
+// Makes the rename itself durable. Not possible on all platforms, so failures are ignored.
+fn sync_parent(path: &Path) {
+    if let Some(parent) = path.parent() {
+        if let Ok(dir) = File::open(parent) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+pub fn backup_dir(data_path: &Path) -> PathBuf {
+    data_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+/* Copies the data file into the backup directory as <name>-<timestamp>.<ext>,
+ * with the timestamp in milliseconds, and removes the oldest backups so that at most `keep` remain.
+ * Returns the path of the new backup, if one was made.
+ */
+pub fn create_backup(data_path: &Path, keep: usize) -> io::Result<Option<PathBuf>> {
+    if keep == 0 || !data_path.exists() {
+        return Ok(None);
+    }
+    let dir = backup_dir(data_path);
+    fs::create_dir_all(&dir)?;
+
+    let stem = file_stem(data_path);
+    let ext = data_path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("bak"));
+    let mut now = chrono::Local::now();
+    let backup = loop {
+        let timestamp = now.format("%Y%m%d-%H%M%S%.3f");
+        let backup = dir.join(format!("{}-{}.{}", stem, timestamp, ext));
+        if !backup.exists() {
+            break backup;
+        }
+        // Taken by a backup made within the same millisecond, so move past it
+        now = now + chrono::Duration::milliseconds(1);
+    };
+    fs::copy(data_path, &backup)?;
+
+    for old in list_backups(data_path).into_iter().skip(keep) {
+        fs::remove_file(old)?;
+    }
+    Ok(Some(backup))
+}
+
+// Backups of the given data file, newest first
+pub fn list_backups(data_path: &Path) -> Vec<PathBuf> {
+    let prefix = format!("{}-", file_stem(data_path));
+    let mut backups: Vec<PathBuf> = match fs::read_dir(backup_dir(data_path)) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .map(|n| n.to_string_lossy().starts_with(&prefix))
+                    .unwrap_or(false)
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    // Timestamps are zero-padded, so lexical order is chronological
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| String::from("timer"))
+}
+
+// Modification time of a file, used to notice writes made by someone else
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_in_quick_succession_are_all_kept() {
+        let dir = std::env::temp_dir().join(format!("timekeeper-backups-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let data_path = dir.join("timer.json");
+        fs::write(&data_path, "{}").unwrap();
+
+        let backups: Vec<PathBuf> = (0..5)
+            .map(|_| create_backup(&data_path, 10).unwrap().unwrap())
+            .collect();
+        let mut listed = list_backups(&data_path);
+        listed.reverse();
+        assert_eq!(listed, backups);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,249 @@
+use crate::config::Config;
+use crate::data::TimekeeperData;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::cmp::Reverse;
+
+// Length of the range shown in the reports screen
+#[derive(Clone, Copy, PartialEq)]
+pub enum Period {
+    Week,
+    Month,
+    Quarter,
+    Year,
+    // Dates typed in by hand
+    Custom,
+}
+
+impl Period {
+    // The period after this one when cycling with Tab. Custom is only entered by typing a range
+    pub fn next(self) -> Period {
+        match self {
+            Period::Week => Period::Month,
+            Period::Month => Period::Quarter,
+            Period::Quarter => Period::Year,
+            Period::Year | Period::Custom => Period::Week,
+        }
+    }
+
+    // First and last day of the period containing `date`; a custom period is the day itself
+    pub fn range(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            Period::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (monday, monday + Duration::days(6))
+            }
+            Period::Month => months_from(date.year(), date.month(), 1),
+            Period::Quarter => months_from(date.year(), (date.month() - 1) / 3 * 3 + 1, 3),
+            Period::Year => months_from(date.year(), 1, 12),
+            Period::Custom => (date, date),
+        }
+    }
+
+    // The range `steps` periods before or after `from..=to`
+    pub fn shift(self, from: NaiveDate, to: NaiveDate, steps: i32) -> (NaiveDate, NaiveDate) {
+        let months = match self {
+            Period::Week => return self.range(from + Duration::weeks(steps as i64)),
+            Period::Month => 1,
+            Period::Quarter => 3,
+            Period::Year => 12,
+            Period::Custom => {
+                let days = (to - from).num_days() + 1;
+                let offset = Duration::days(days * steps as i64);
+                return (from + offset, to + offset);
+            }
+        };
+        // Months counted from year 0, so the arithmetic can cross years
+        let month = from.year() * 12 + from.month0() as i32 + months * steps;
+        self.range(NaiveDate::from_ymd(
+            month.div_euclid(12),
+            month.rem_euclid(12) as u32 + 1,
+            1,
+        ))
+    }
+}
+
+// `count` whole months starting with `month` in `year`
+fn months_from(year: i32, month: u32, count: u32) -> (NaiveDate, NaiveDate) {
+    let first = NaiveDate::from_ymd(year, month, 1);
+    let end = month - 1 + count;
+    let next = NaiveDate::from_ymd(year + (end / 12) as i32, end % 12 + 1, 1);
+    (first, next - Duration::days(1))
+}
+
+// Chart shown at the bottom of the reports screen
+#[derive(Clone, Copy, PartialEq)]
+pub enum Breakdown {
+    Timecodes,
+    Clients,
+}
+
+// Hours logged between two dates, summed per timecode, client and day
+pub struct Report {
+    pub period: Period,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    // Largest first
+    pub timecodes: Vec<(String, u32)>,
+    // Timecodes without a client in the registry are summed under ""
+    pub clients: Vec<(String, u32)>,
+    // Days with hours logged, in order
+    pub days: Vec<(NaiveDate, u32)>,
+    pub total: u32,
+}
+
+impl Report {
+    pub fn new(
+        data: &TimekeeperData,
+        conf: &Config,
+        period: Period,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Report {
+        let mut timecodes: Vec<(String, u32)> = vec![];
+        let mut clients: Vec<(String, u32)> = vec![];
+        let mut days: Vec<(NaiveDate, u32)> = vec![];
+        for record in data.days_between(from, to) {
+            if record.minutes == 0 {
+                continue;
+            }
+            let client = conf
+                .timecode_info(&record.timecode)
+                .map(|info| info.client.clone())
+                .unwrap_or_default();
+            add(&mut timecodes, record.timecode, record.minutes);
+            add(&mut clients, client, record.minutes);
+            match days.last_mut() {
+                Some((date, minutes)) if *date == record.date => *minutes += record.minutes,
+                _ => days.push((record.date, record.minutes)),
+            }
+        }
+        // Stable, so ties keep the order they were first logged in
+        timecodes.sort_by_key(|(_, m)| Reverse(*m));
+        clients.sort_by_key(|(_, m)| Reverse(*m));
+        let total = days.iter().map(|(_, m)| m).sum();
+        Report {
+            period,
+            from,
+            to,
+            timecodes,
+            clients,
+            days,
+            total,
+        }
+    }
+}
+
+fn add(totals: &mut Vec<(String, u32)>, key: String, minutes: u32) {
+    match totals.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, m)) => *m += minutes,
+        None => totals.push((key, minutes)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(y, m, d)
+    }
+
+    #[test]
+    fn ranges_cover_whole_periods() {
+        let cases = [
+            (
+                Period::Week,
+                date(2026, 1, 1),
+                date(2025, 12, 29),
+                date(2026, 1, 4),
+            ),
+            (
+                Period::Month,
+                date(2024, 2, 10),
+                date(2024, 2, 1),
+                date(2024, 2, 29),
+            ),
+            (
+                Period::Month,
+                date(2026, 12, 31),
+                date(2026, 12, 1),
+                date(2026, 12, 31),
+            ),
+            (
+                Period::Quarter,
+                date(2026, 11, 5),
+                date(2026, 10, 1),
+                date(2026, 12, 31),
+            ),
+            (
+                Period::Quarter,
+                date(2026, 3, 31),
+                date(2026, 1, 1),
+                date(2026, 3, 31),
+            ),
+            (
+                Period::Year,
+                date(2026, 6, 15),
+                date(2026, 1, 1),
+                date(2026, 12, 31),
+            ),
+            (
+                Period::Custom,
+                date(2026, 6, 15),
+                date(2026, 6, 15),
+                date(2026, 6, 15),
+            ),
+        ];
+        for (period, day, from, to) in cases {
+            assert_eq!(period.range(day), (from, to), "{}", day);
+        }
+    }
+
+    #[test]
+    fn shifting_crosses_month_and_year_ends() {
+        let december = Period::Month.range(date(2026, 12, 10));
+        assert_eq!(
+            Period::Month.shift(december.0, december.1, 1),
+            (date(2027, 1, 1), date(2027, 1, 31))
+        );
+        let january = Period::Month.range(date(2026, 1, 10));
+        assert_eq!(
+            Period::Month.shift(january.0, january.1, -1),
+            (date(2025, 12, 1), date(2025, 12, 31))
+        );
+        assert_eq!(
+            Period::Month.shift(january.0, january.1, 13),
+            (date(2027, 2, 1), date(2027, 2, 28))
+        );
+
+        let q1 = Period::Quarter.range(date(2026, 2, 1));
+        assert_eq!(
+            Period::Quarter.shift(q1.0, q1.1, -1),
+            (date(2025, 10, 1), date(2025, 12, 31))
+        );
+        let year = Period::Year.range(date(2026, 2, 1));
+        assert_eq!(
+            Period::Year.shift(year.0, year.1, -2),
+            (date(2024, 1, 1), date(2024, 12, 31))
+        );
+
+        let week = Period::Week.range(date(2025, 12, 24));
+        assert_eq!(
+            Period::Week.shift(week.0, week.1, 1),
+            (date(2025, 12, 29), date(2026, 1, 4))
+        );
+    }
+
+    #[test]
+    fn custom_ranges_shift_by_their_length() {
+        let (from, to) = (date(2026, 12, 28), date(2027, 1, 3));
+        assert_eq!(
+            Period::Custom.shift(from, to, 1),
+            (date(2027, 1, 4), date(2027, 1, 10))
+        );
+        assert_eq!(
+            Period::Custom.shift(from, to, -1),
+            (date(2026, 12, 21), date(2026, 12, 27))
+        );
+    }
+}
@@ -0,0 +1,630 @@
+use crate::data::{date_of, weeks_in_year, Day, TimekeeperData};
+use crate::persist::write_atomic;
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/* Version of the data file layout written by this build. Whenever the
+ * serialized shape of TimekeeperData changes, bump this and append a step
+ * to MIGRATIONS that upgrades the previous version.
+ */
+pub const SCHEMA_VERSION: u32 = 7;
+
+// MIGRATIONS[n] upgrades the `data` payload from version n to version n + 1
+const MIGRATIONS: [fn(Value) -> Value; SCHEMA_VERSION as usize] = [
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7,
+];
+
+// Top-level envelope of timer.json
+#[derive(Serialize)]
+struct DataFile<'a> {
+    version: u32,
+    data: &'a TimekeeperData,
+}
+
+#[derive(Deserialize)]
+struct RawDataFile {
+    version: u32,
+    data: Value,
+}
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(io::Error),
+    Corrupt(serde_json::Error),
+    NewerVersion { found: u32, supported: u32 },
+    Database(rusqlite::Error),
+    InvalidDate(String),
+    InvalidAbsence(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io(e) => write!(f, "Unable to access data file: {}", e),
+            DataError::Corrupt(e) => write!(f, "Data file is corrupted: {}", e),
+            DataError::NewerVersion { found, supported } => write!(
+                f,
+                "Data file was written by a newer version of timekeeper \
+                 (schema version {}, this version supports up to {}). Please upgrade timekeeper.",
+                found, supported
+            ),
+            DataError::Database(e) => write!(f, "Database error: {}", e),
+            DataError::InvalidDate(d) => write!(f, "Invalid date in data: {}", d),
+            DataError::InvalidAbsence(a) => write!(f, "Unknown absence in data: {}", a),
+        }
+    }
+}
+
+impl Error for DataError {}
+
+impl From<io::Error> for DataError {
+    fn from(e: io::Error) -> Self {
+        DataError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for DataError {
+    fn from(e: rusqlite::Error) -> Self {
+        DataError::Database(e)
+    }
+}
+
+impl From<serde_json::Error> for DataError {
+    fn from(e: serde_json::Error) -> Self {
+        DataError::Corrupt(e)
+    }
+}
+
+pub fn to_json(data: &TimekeeperData) -> String {
+    serde_json::to_string_pretty(&DataFile {
+        version: SCHEMA_VERSION,
+        data,
+    })
+    .expect("ERR: Unable to convert data to JSON!")
+}
+
+// Parses a data file of any known version, returning the payload upgraded to SCHEMA_VERSION
+pub fn from_json(contents: &str) -> Result<(TimekeeperData, u32), DataError> {
+    let (version, mut data) = split_envelope(serde_json::from_str(contents)?)?;
+    if version > SCHEMA_VERSION {
+        return Err(DataError::NewerVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    for migration in MIGRATIONS[version as usize..].iter() {
+        data = migration(data);
+    }
+    Ok((serde_json::from_value(data)?, version))
+}
+
+/* Reads the data file at `path`. Returns None if there is no file yet.
+ * Files from older versions are upgraded in place, after copying the original
+ * to `<path>.v<version>.bak`.
+ */
+pub fn read_data(path: &Path) -> Result<Option<TimekeeperData>, DataError> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let (data, version) = from_json(&contents)?;
+    if version < SCHEMA_VERSION {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(format!(".v{}.bak", version));
+        if !Path::new(&backup).exists() {
+            fs::write(&backup, &contents)?;
+        }
+        write_atomic(path, to_json(&data).as_bytes())?;
+    }
+    Ok(Some(data))
+}
+
+// Files written before versioning are a bare map of years
+fn split_envelope(value: Value) -> Result<(u32, Value), DataError> {
+    match value {
+        Value::Object(ref obj) if obj.contains_key("version") => {
+            let file: RawDataFile = serde_json::from_value(value)?;
+            Ok((file.version, file.data))
+        }
+        v => Ok((0, v)),
+    }
+}
+
+// v1 introduced the envelope itself; the payload is unchanged
+fn v0_to_v1(data: Value) -> Value {
+    data
+}
+
+// v2 stores durations as integer minutes instead of float hours
+fn v1_to_v2(mut data: Value) -> Value {
+    for day in days_mut(&mut data) {
+        if let Some(hours) = day.remove("hours") {
+            let minutes = (hours.as_f64().unwrap_or(0.0) * 60.0).round().max(0.0) as u64;
+            day.insert(String::from("minutes"), Value::from(minutes));
+        }
+    }
+    data
+}
+
+/* v3 adds optional time entries to days. Nothing to convert, but older
+ * versions would silently drop the entries when saving, so they must refuse the file.
+ */
+fn v2_to_v3(data: Value) -> Value {
+    data
+}
+
+// v4 moves the years into a field, making room for the running timer next to them
+fn v3_to_v4(data: Value) -> Value {
+    let mut payload = Map::new();
+    payload.insert(String::from("years"), data);
+    Value::Object(payload)
+}
+
+/* v5 keys weeks by ISO week-year. Earlier versions used the calendar year, so
+ * days early in January that belong to week 53 of the previous year were stored
+ * as week 53 of a year that has none, and days late in December that belong to
+ * week 1 of the next year were stored in week 1 of their own. Both are moved,
+ * merging into the right week if it exists. Days whose slot was also used by the
+ * real week can't be told apart and are left where they are.
+ */
+fn v4_to_v5(mut data: Value) -> Value {
+    if let Some(years) = data.get_mut("years").and_then(|y| y.as_object_mut()) {
+        let misfiled: Vec<(String, usize)> = years
+            .keys()
+            .filter_map(|k| Some((k.clone(), k.parse::<usize>().ok()?)))
+            .filter(|(_, y)| *y > 0 && weeks_in_year(*y) < 53 && weeks_in_year(y - 1) == 53)
+            .collect();
+        for (key, year) in misfiled {
+            let week = match years
+                .get_mut(&key)
+                .and_then(|y| y.as_object_mut())
+                .and_then(|y| y.remove("53"))
+            {
+                Some(Value::Array(w)) => w,
+                _ => continue,
+            };
+            let target = years
+                .entry((year - 1).to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(target) = target.as_object_mut() {
+                let target_week = target.entry("53").or_insert_with(|| Value::Array(vec![]));
+                if let Some(target_week) = target_week.as_array_mut() {
+                    merge_week_values(target_week, week);
+                }
+            }
+        }
+
+        // Taken out of every year before merging, so no day is moved twice
+        let mut late: Vec<(usize, Vec<Value>)> = vec![];
+        for (key, weeks) in years.iter_mut() {
+            let year = match key.parse::<usize>() {
+                Ok(y) => y,
+                Err(_) => continue,
+            };
+            if let Some(week) = weeks.get_mut("1").and_then(|w| w.as_array_mut()) {
+                late.push((year, take_late_december(week, year)));
+            }
+        }
+        for (year, moved) in late.into_iter().filter(|(_, m)| !m.is_empty()) {
+            let target = years
+                .entry((year + 1).to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Some(target) = target.as_object_mut() {
+                let target_week = target.entry("1").or_insert_with(|| Value::Array(vec![]));
+                if let Some(target_week) = target_week.as_array_mut() {
+                    merge_week_values(target_week, moved);
+                }
+            }
+        }
+    }
+    if let Some(timer) = data.get_mut("timer").and_then(|t| t.as_object_mut()) {
+        let year = timer.get("year").and_then(|y| y.as_u64()).unwrap_or(0) as usize;
+        let week = timer.get("week").and_then(|w| w.as_u64()).unwrap_or(0);
+        let day = timer.get("day").and_then(|d| d.as_u64()).unwrap_or(0) as u8;
+        if week == 53 && year > 0 && weeks_in_year(year) < 53 {
+            timer.insert(String::from("year"), Value::from(year - 1));
+        } else if week == 1 && late_december(year, day) {
+            timer.insert(String::from("year"), Value::from(year + 1));
+        }
+    }
+    data
+}
+
+/* v6 adds manual flex adjustments next to the years. Older versions would drop
+ * them when saving, so they must refuse the file.
+ */
+fn v5_to_v6(mut data: Value) -> Value {
+    if let Some(payload) = data.as_object_mut() {
+        payload
+            .entry("flex_adjustments")
+            .or_insert_with(|| Value::Array(vec![]));
+    }
+    data
+}
+
+// v7 adds absences by date, which older versions would likewise drop
+fn v6_to_v7(mut data: Value) -> Value {
+    if let Some(payload) = data.as_object_mut() {
+        payload
+            .entry("absences")
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    data
+}
+
+const DAY_KEYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/* Whether a day of week 1 stored under `year` by calendar year can only have
+ * been logged in late December. True when the real week 1 has that weekday in
+ * the previous year, where it would have been stored instead.
+ */
+fn late_december(year: usize, day: u8) -> bool {
+    let in_year = |week_year: usize, calendar_year: usize| {
+        date_of(week_year, 1, day)
+            .map(|d| d.year() as usize == calendar_year)
+            .unwrap_or(false)
+    };
+    year > 0 && in_year(year, year - 1) && in_year(year + 1, year)
+}
+
+// Removes the late December days from week 1 of `year`, returning them as timecodes of their own
+fn take_late_december(week: &mut Vec<Value>, year: usize) -> Vec<Value> {
+    let mut moved = vec![];
+    for tc in week.iter_mut().filter_map(|t| t.as_object_mut()) {
+        let mut taken = Map::new();
+        for (day, key) in DAY_KEYS.iter().enumerate() {
+            if late_december(year, day as u8) {
+                if let Some(value) = tc.remove(*key) {
+                    taken.insert(key.to_string(), value);
+                }
+            }
+        }
+        if !taken.is_empty() {
+            if let Some(name) = tc.get("timecode") {
+                taken.insert(String::from("timecode"), name.clone());
+            }
+            moved.push(Value::Object(taken));
+        }
+    }
+    // Timecodes whose days were all moved go with them
+    week.retain(|tc| {
+        tc.as_object()
+            .map(|tc| DAY_KEYS.iter().any(|k| tc.contains_key(*k)))
+            .unwrap_or(true)
+            || !moved
+                .iter()
+                .any(|m| m.get("timecode") == tc.get("timecode"))
+    });
+    moved
+}
+
+// Adds the timecodes of `week` to `target`, combining days logged on both
+fn merge_week_values(target: &mut Vec<Value>, week: Vec<Value>) {
+    for tc in week {
+        let name = tc.get("timecode").cloned();
+        let existing = target
+            .iter_mut()
+            .find(|t| t.get("timecode") == name.as_ref())
+            .and_then(|t| t.as_object_mut());
+        let (existing, tc) = match (existing, tc) {
+            (Some(e), Value::Object(tc)) => (e, tc),
+            (_, tc) => {
+                target.push(tc);
+                continue;
+            }
+        };
+        for (key, day) in tc {
+            match existing.get_mut(&key) {
+                Some(Value::Object(old)) if key != "timecode" => {
+                    if let Value::Object(day) = day {
+                        merge_day_values(old, day);
+                    }
+                }
+                Some(_) => (),
+                None => {
+                    existing.insert(key, day);
+                }
+            }
+        }
+    }
+}
+
+// Combines two days the way Day::merge does. Left as they are if either can't be read
+fn merge_day_values(day: &mut Map<String, Value>, other: Map<String, Value>) {
+    let parse = |mut d: Map<String, Value>| {
+        d.entry("minutes").or_insert_with(|| Value::from(0));
+        d.entry("comment").or_insert_with(|| Value::from(""));
+        serde_json::from_value::<Day>(Value::Object(d))
+    };
+    if let (Ok(mut merged), Ok(other)) = (parse(day.clone()), parse(other)) {
+        merged.merge(other);
+        if let Ok(Value::Object(merged)) = serde_json::to_value(&merged) {
+            *day = merged;
+        }
+    }
+}
+
+// Every day object in a v1-v3 payload shaped as years -> weeks -> [timecode]
+fn days_mut(data: &mut Value) -> Vec<&mut Map<String, Value>> {
+    let mut days = vec![];
+    let years = match data.as_object_mut() {
+        Some(y) => y,
+        None => return days,
+    };
+    for weeks in years.values_mut().filter_map(|y| y.as_object_mut()) {
+        for week in weeks.values_mut().filter_map(|w| w.as_array_mut()) {
+            for tc in week.iter_mut().filter_map(|t| t.as_object_mut()) {
+                for (key, day) in tc.iter_mut() {
+                    if key != "timecode" {
+                        if let Some(d) = day.as_object_mut() {
+                            days.push(d);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    days
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unversioned_file_is_upgraded_to_current_version() {
+        let v0 =
+            r#"{"2026": {"42": [{"timecode": "ACME", "monday": {"hours": 1.5, "comment": "x"}}]}}"#;
+        let (data, version) = from_json(v0).unwrap();
+        assert_eq!(version, 0);
+        let day = data.get(2026).unwrap().get(42).unwrap().0[0]
+            .get(0)
+            .unwrap();
+        assert_eq!(day.minutes, 90);
+        assert_eq!(day.comment, "x");
+
+        // Written back at the current version, which then needs no migration
+        let json = to_json(&data);
+        let (again, version) = from_json(&json).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        let day = again.get(2026).unwrap().get(42).unwrap().0[0]
+            .get(0)
+            .unwrap();
+        assert_eq!(day.minutes, 90);
+    }
+
+    #[test]
+    fn every_version_migrates_to_current() {
+        for version in 1..=SCHEMA_VERSION {
+            let payload = if version < 4 {
+                r#"{"2026": {"42": []}}"#
+            } else {
+                r#"{"years": {"2026": {"42": []}}}"#
+            };
+            let file = format!(r#"{{"version": {}, "data": {}}}"#, version, payload);
+            let (data, found) = from_json(&file).unwrap();
+            assert_eq!(found, version);
+            assert!(data.get(2026).and_then(|y| y.get(42)).is_some());
+        }
+    }
+
+    #[test]
+    fn hours_become_whole_minutes() {
+        let v1 = serde_json::json!({"2026": {"42": [{
+            "timecode": "ACME",
+            "monday": {"hours": 1.5, "comment": ""},
+            "tuesday": {"hours": 0.3333, "comment": ""},
+            "wednesday": {"hours": -2.0, "comment": ""}
+        }]}});
+        let v2 = v1_to_v2(v1);
+        let tc = &v2["2026"]["42"][0];
+        assert_eq!(tc["monday"]["minutes"], 90);
+        assert_eq!(tc["tuesday"]["minutes"], 20);
+        assert_eq!(tc["wednesday"]["minutes"], 0);
+        assert!(tc["monday"].get("hours").is_none());
+    }
+
+    // Day of a stored timecode as (minutes, comment)
+    fn day(
+        data: &TimekeeperData,
+        year: usize,
+        week: u8,
+        tc: &str,
+        day: u8,
+    ) -> Option<(u32, String)> {
+        let week = data.get(year)?.get(week)?;
+        let d = week.0.iter().find(|t| t.timecode == tc)?.get(day)?;
+        Some((d.minutes, d.comment.clone()))
+    }
+
+    #[test]
+    fn weeks_filed_by_calendar_year_are_moved() {
+        let v4 = r#"{"version": 4, "data": {
+            "years": {
+                "2024": {"1": [{"timecode": "ACME", "monday": {"minutes": 60, "comment": "Jan 1"}}]},
+                "2025": {"1": [
+                    {"timecode": "ACME",
+                     "monday": {"minutes": 120, "comment": "Dec 29"},
+                     "wednesday": {"minutes": 60, "comment": "Jan 1"}},
+                    {"timecode": "Support", "tuesday": {"minutes": 30, "comment": "Dec 30"}}
+                ]},
+                "2026": {
+                    "1": [{"timecode": "Internal", "thursday": {"minutes": 45, "comment": ""}}],
+                    "53": [{"timecode": "ACME", "friday": {"minutes": 30, "comment": "x"}}]
+                },
+                "2027": {"53": [{"timecode": "ACME",
+                    "friday": {"minutes": 60, "comment": "new year"},
+                    "saturday": {"minutes": 15, "comment": ""}}]}
+            },
+            "timer": {"year": 2025, "week": 1, "timecode": "Support", "day": 1,
+                      "started": "2025-12-30T09:00:00+01:00"}
+        }}"#;
+        let (data, _) = from_json(v4).unwrap();
+
+        // Jan 1 2027 belongs to week 53 of 2026, merging with the day logged there
+        assert!(data.get(2027).and_then(|y| y.get(53)).is_none());
+        let friday = day(&data, 2026, 53, "ACME", 4);
+        assert_eq!(friday, Some((90, String::from("x; new year"))));
+        assert_eq!(day(&data, 2026, 53, "ACME", 5), Some((15, String::new())));
+
+        // Dec 29 and 30 2025 belong to week 1 of 2026, after the timecodes already there
+        let week: Vec<&str> = data
+            .get(2026)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .0
+            .iter()
+            .map(|tc| tc.timecode.as_str())
+            .collect();
+        assert_eq!(week, ["Internal", "ACME", "Support"]);
+        assert_eq!(
+            day(&data, 2026, 1, "ACME", 0),
+            Some((120, String::from("Dec 29")))
+        );
+        assert_eq!(
+            day(&data, 2026, 1, "Support", 1),
+            Some((30, String::from("Dec 30")))
+        );
+        assert_eq!(day(&data, 2025, 1, "ACME", 0), None);
+        assert_eq!(
+            day(&data, 2025, 1, "ACME", 2),
+            Some((60, String::from("Jan 1")))
+        );
+        assert!(data
+            .get(2025)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .0
+            .iter()
+            .all(|tc| tc.timecode != "Support"));
+        assert_eq!(data.timer.as_ref().map(|t| t.year), Some(2026));
+
+        // Jan 1 2024 and Dec 30 2024 shared this slot, so it stays
+        assert_eq!(
+            day(&data, 2024, 1, "ACME", 0),
+            Some((60, String::from("Jan 1")))
+        );
+    }
+
+    // Start and end of each entry of a stored day
+    fn entries(data: &TimekeeperData, year: usize, week: u8, tc: &str, day: u8) -> Vec<String> {
+        let week = data.get(year).unwrap().get(week).unwrap();
+        let d = week
+            .0
+            .iter()
+            .find(|t| t.timecode == tc)
+            .unwrap()
+            .get(day)
+            .unwrap();
+        d.entries
+            .iter()
+            .map(|e| e.to_string().trim().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn colliding_days_with_and_without_entries_keep_all_hours() {
+        let v4 = r#"{"version": 4, "data": {"years": {
+            "2026": {"53": [{"timecode": "ACME",
+                "friday": {"minutes": 60, "comment": "", "entries": [{"start": "09:00", "end": "10:00"}]},
+                "saturday": {"minutes": 30, "comment": ""}}]},
+            "2027": {"53": [{"timecode": "ACME",
+                "friday": {"minutes": 90, "comment": ""},
+                "saturday": {"minutes": 60, "comment": "", "entries": [{"start": "08:00", "end": "09:00"}]}}]}
+        }}}"#;
+        let (data, _) = from_json(v4).unwrap();
+        assert_eq!(day(&data, 2026, 53, "ACME", 4).unwrap().0, 150);
+        assert_eq!(
+            entries(&data, 2026, 53, "ACME", 4),
+            ["09:00-10:00", "10:00-11:30"]
+        );
+        assert_eq!(day(&data, 2026, 53, "ACME", 5).unwrap().0, 90);
+        assert_eq!(
+            entries(&data, 2026, 53, "ACME", 5),
+            ["08:00-09:00", "09:00-09:30"]
+        );
+    }
+
+    #[test]
+    fn late_december_days_move_one_year_only() {
+        let v4 = r#"{"version": 4, "data": {"years": {
+            "2013": {"1": [{"timecode": "ACME", "monday": {"minutes": 60, "comment": "Dec 30 2013"}}]},
+            "2014": {"1": [{"timecode": "ACME", "monday": {"minutes": 30, "comment": "Dec 29 2014"}}]}
+        }}}"#;
+        let (data, _) = from_json(v4).unwrap();
+        let dec_2013 = Some((60, String::from("Dec 30 2013")));
+        assert_eq!(day(&data, 2014, 1, "ACME", 0), dec_2013);
+        let dec_2014 = Some((30, String::from("Dec 29 2014")));
+        assert_eq!(day(&data, 2015, 1, "ACME", 0), dec_2014);
+    }
+
+    #[test]
+    fn late_december_is_only_decided_when_unambiguous() {
+        // Monday of week 1 was in the previous December three years running
+        assert!(!late_december(2012, 0));
+        assert!(late_december(2013, 0));
+        assert!(late_december(2014, 0));
+        assert!(!late_december(2015, 0));
+        // Week 1 of 2025 starts on Dec 30 2024, of 2026 on Dec 29 2025
+        assert!(late_december(2025, 1));
+        assert!(!late_december(2025, 2));
+        assert!(!late_december(2024, 0));
+    }
+
+    #[test]
+    fn newer_version_is_refused() {
+        let file = format!(r#"{{"version": {}, "data": {{}}}}"#, SCHEMA_VERSION + 1);
+        match from_json(&file) {
+            Err(DataError::NewerVersion { found, supported }) => {
+                assert_eq!(found, SCHEMA_VERSION + 1);
+                assert_eq!(supported, SCHEMA_VERSION);
+            }
+            _ => panic!("expected NewerVersion"),
+        }
+    }
+
+    #[test]
+    fn flex_adjustments_are_kept_from_version_6() {
+        let v5 = r#"{"version": 5, "data": {"years": {}}}"#;
+        let (data, _) = from_json(v5).unwrap();
+        assert!(data.flex_adjustments.is_empty());
+
+        let v6 = r#"{"version": 6, "data": {"years": {}, "flex_adjustments": [
+            {"date": "2026-10-12", "minutes": -90, "comment": "paid out"}
+        ]}}"#;
+        let (data, _) = from_json(v6).unwrap();
+        assert_eq!(data.flex_adjustments.len(), 1);
+        assert_eq!(data.flex_adjustments[0].minutes, -90);
+    }
+
+    #[test]
+    fn absences_are_kept_from_version_7() {
+        let v6 = r#"{"version": 6, "data": {"years": {}}}"#;
+        let (data, _) = from_json(v6).unwrap();
+        assert!(data.absences.is_empty());
+
+        let v7 = r#"{"version": 7, "data": {"years": {}, "absences": {
+            "2026-10-12": {"kind": "Vacation", "minutes": 450}
+        }}}"#;
+        let (data, _) = from_json(v7).unwrap();
+        let date = chrono::NaiveDate::from_ymd(2026, 10, 12);
+        assert_eq!(data.absences.get(&date).map(|a| a.minutes), Some(450));
+    }
+}
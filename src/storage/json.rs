@@ -0,0 +1,84 @@
+use super::Storage;
+use crate::data::{Day, DayRecord, TimekeeperData, Week};
+use crate::persist::write_atomic;
+use crate::schema::{self, DataError};
+use chrono::NaiveDate;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The whole of TimekeeperData in one file, rewritten on every save
+pub struct JsonStorage {
+    path: PathBuf,
+    // Loads leave older files as they are instead of upgrading them
+    read_only: bool,
+}
+
+impl JsonStorage {
+    pub fn new(path: PathBuf) -> JsonStorage {
+        JsonStorage {
+            path,
+            read_only: false,
+        }
+    }
+
+    pub fn read_only(path: PathBuf) -> JsonStorage {
+        JsonStorage {
+            path,
+            read_only: true,
+        }
+    }
+
+    // Like `load`, but never upgrades the file in place
+    pub fn read_only_load(&self) -> Result<TimekeeperData, DataError> {
+        let (data, _) = schema::from_json(&fs::read_to_string(&self.path)?)?;
+        Ok(data)
+    }
+}
+
+impl Storage for JsonStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Result<TimekeeperData, DataError> {
+        if self.read_only {
+            if !self.path.exists() {
+                return Ok(TimekeeperData::default());
+            }
+            return self.read_only_load();
+        }
+        Ok(schema::read_data(&self.path)?.unwrap_or_default())
+    }
+
+    fn load_week(&mut self, year: usize, week: u8) -> Result<Option<Week>, DataError> {
+        let mut data = self.load()?;
+        Ok(data.get_mut(year).and_then(|y| y.0.remove(&week)))
+    }
+
+    fn save(
+        &mut self,
+        data: &TimekeeperData,
+        _weeks: &HashSet<(usize, u8)>,
+    ) -> Result<(), DataError> {
+        write_atomic(&self.path, schema::to_json(data).as_bytes())?;
+        Ok(())
+    }
+
+    fn save_day(
+        &mut self,
+        year: usize,
+        week: u8,
+        timecode: &str,
+        day: u8,
+        value: &Day,
+    ) -> Result<(), DataError> {
+        let mut data = self.load()?;
+        data.set_day(year, week, timecode, day, value.clone());
+        self.save(&data, &HashSet::new())
+    }
+
+    fn query_range(&mut self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayRecord>, DataError> {
+        Ok(self.load()?.days_between(from, to))
+    }
+}
@@ -0,0 +1,93 @@
+mod json;
+mod sqlite;
+
+use crate::data::{Day, DayRecord, TimekeeperData, Week};
+use crate::schema::DataError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+pub use self::json::JsonStorage;
+pub use self::sqlite::SqliteStorage;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum StorageKind {
+    // A single pretty-printed timer.json
+    Json,
+    // An embedded SQLite database, timer.sqlite
+    Sqlite,
+}
+
+/* Where timekeeping data is persisted. The app keeps the full TimekeeperData
+ * in memory; backends that can do so should only touch the weeks that changed.
+ * The week, day and range methods serve consumers that don't load everything.
+ */
+pub trait Storage {
+    fn path(&self) -> &Path;
+    fn load(&mut self) -> Result<TimekeeperData, DataError>;
+    fn load_week(&mut self, year: usize, week: u8) -> Result<Option<Week>, DataError>;
+    // Persists `weeks` of `data`. A week missing from `data` is removed from storage
+    fn save(
+        &mut self,
+        data: &TimekeeperData,
+        weeks: &HashSet<(usize, u8)>,
+    ) -> Result<(), DataError>;
+    fn save_day(
+        &mut self,
+        year: usize,
+        week: u8,
+        timecode: &str,
+        day: u8,
+        value: &Day,
+    ) -> Result<(), DataError>;
+    // Every logged day between `from` and `to`, inclusive, ordered by date
+    fn query_range(&mut self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayRecord>, DataError>;
+}
+
+pub fn data_path(kind: StorageKind, dir: &Path) -> PathBuf {
+    match kind {
+        StorageKind::Json => dir.join("timer.json"),
+        StorageKind::Sqlite => dir.join("timer.sqlite"),
+    }
+}
+
+/* Opens the configured backend in `dir`. When switching to SQLite for the first
+ * time, the existing timer.json is imported into the new database.
+ */
+pub fn open(kind: StorageKind, dir: &Path) -> Result<Box<dyn Storage>, DataError> {
+    let path = data_path(kind, dir);
+    match kind {
+        StorageKind::Json => Ok(Box::new(JsonStorage::new(path))),
+        StorageKind::Sqlite => {
+            let json_path = data_path(StorageKind::Json, dir);
+            let import = !path.exists() && json_path.exists();
+            let mut storage = SqliteStorage::open(path)?;
+            if import {
+                let data = JsonStorage::new(json_path).load()?;
+                let weeks = data.week_keys().into_iter().collect();
+                storage.save(&data, &weeks)?;
+            }
+            Ok(Box::new(storage))
+        }
+    }
+}
+
+/* Opens the configured backend in `dir` without writing to it, not even to
+ * upgrade an older file. Used while another instance holds the lock.
+ */
+pub fn open_read_only(kind: StorageKind, dir: &Path) -> Result<Box<dyn Storage>, DataError> {
+    let path = data_path(kind, dir);
+    match kind {
+        StorageKind::Json => Ok(Box::new(JsonStorage::read_only(path))),
+        StorageKind::Sqlite => Ok(Box::new(SqliteStorage::open_read_only(path)?)),
+    }
+}
+
+// Reads a standalone data file, such as a backup, picking the backend from its extension
+pub fn load_file(path: &Path) -> Result<TimekeeperData, DataError> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("sqlite") => SqliteStorage::open_read_only(path.to_path_buf())?.load(),
+        _ => JsonStorage::new(path.to_path_buf()).read_only_load(),
+    }
+}
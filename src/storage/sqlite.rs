@@ -0,0 +1,642 @@
+use super::Storage;
+use crate::data::{
+    date_of, Absence, AbsenceKind, Day, DayRecord, Entry, Timecode, TimekeeperData, Week,
+};
+use crate::schema::DataError;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/* Layout of the database, stored in `PRAGMA user_version`. Bump it, update
+ * CREATE_TABLES and add a step to MIGRATIONS whenever the tables change.
+ */
+const SQLITE_SCHEMA_VERSION: u32 = 6;
+
+/* Upsert clause adding a day onto the one already stored, as Day::merge does.
+ * Hours logged without entries next to a day with them become an entry after
+ * its last one, since the total is taken from the entries.
+ */
+macro_rules! merge_days_on_conflict {
+    () => {
+        "ON CONFLICT DO UPDATE SET
+             minutes = days.minutes + excluded.minutes,
+             comment = CASE
+                 WHEN days.comment = '' THEN excluded.comment
+                 WHEN excluded.comment = '' THEN days.comment
+                 ELSE days.comment || '; ' || excluded.comment END,
+             entries = CASE
+                 WHEN days.entries IS NULL AND excluded.entries IS NULL THEN NULL
+                 ELSE (SELECT json_group_array(json(value)) FROM (
+                     SELECT value FROM (
+                         SELECT value FROM json_each(COALESCE(days.entries, '[]'))
+                         UNION ALL SELECT value FROM json_each(COALESCE(excluded.entries, '[]'))
+                         UNION ALL SELECT json_object('start', last_end, 'end',
+                             strftime('%H:%M', time(last_end, printf('+%d minutes', bare))))
+                         FROM (SELECT
+                             CASE WHEN days.entries IS NULL
+                                 THEN days.minutes ELSE excluded.minutes END AS bare,
+                             (SELECT json_extract(value, '$.end')
+                                 FROM json_each(COALESCE(days.entries, excluded.entries))
+                                 ORDER BY json_extract(value, '$.start') DESC LIMIT 1) AS last_end)
+                         WHERE bare > 0 AND (days.entries IS NULL) != (excluded.entries IS NULL))
+                     ORDER BY json_extract(value, '$.start'))) END;"
+    };
+}
+
+// MIGRATIONS[n] upgrades the database from version n + 1 to n + 2
+const MIGRATIONS: [&str; SQLITE_SCHEMA_VERSION as usize - 1] = [
+    // v2: durations in whole minutes
+    "ALTER TABLE days ADD COLUMN minutes INTEGER NOT NULL DEFAULT 0;
+     UPDATE days SET minutes = CAST(ROUND(MAX(hours, 0) * 60) AS INTEGER);
+     ALTER TABLE days DROP COLUMN hours;",
+    // v3: time entries, as a JSON array
+    "ALTER TABLE days ADD COLUMN entries TEXT;",
+    // v4: values stored outside the weeks, as JSON keyed by name
+    "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);",
+    /* v5: weeks keyed by ISO week-year. Week 53 of a year without one was
+     * stored by calendar year and belongs to the previous year, which then has
+     * a week 53 starting 13 days before the Sunday of its following week 1.
+     * Days of week 1 whose real date is in the previous December can only have
+     * been logged late in December, in week 1 of the next year. Those are copied
+     * aside and removed first, so none is moved twice.
+     */
+    concat!(
+        "CREATE TEMP TABLE misfiled AS
+         SELECT DISTINCT year FROM timecodes WHERE week = 53
+         AND julianday(date(printf('%04d-01-04', year + 1), 'weekday 0'))
+           - julianday(date(printf('%04d-01-04', year), 'weekday 0')) = 364
+         AND julianday(date(printf('%04d-01-04', year), 'weekday 0'))
+           - julianday(date(printf('%04d-01-04', year - 1), 'weekday 0')) = 371;
+     INSERT INTO timecodes (year, week, position, timecode)
+         SELECT year - 1, 53, position + 1000, timecode FROM timecodes
+         WHERE week = 53 AND year IN (SELECT year FROM misfiled)
+         ON CONFLICT DO NOTHING;
+     INSERT INTO days (year, week, timecode, weekday, date, minutes, comment, entries)
+         SELECT year - 1, 53, timecode, weekday,
+             date(printf('%04d-01-04', year), 'weekday 0', printf('%+d days', weekday - 13)),
+             minutes, comment, entries FROM days
+         WHERE week = 53 AND year IN (SELECT year FROM misfiled)
+         ",
+        merge_days_on_conflict!(),
+        "
+     DELETE FROM days WHERE week = 53 AND year IN (SELECT year FROM misfiled);
+     DELETE FROM timecodes WHERE week = 53 AND year IN (SELECT year FROM misfiled);
+     UPDATE meta SET value = json_set(value, '$.year', json_extract(value, '$.year') - 1)
+         WHERE key = 'timer' AND json_extract(value, '$.week') = 53
+         AND json_extract(value, '$.year') IN (SELECT year FROM misfiled);
+     DROP TABLE misfiled;
+     CREATE TEMP TABLE late_december AS
+         SELECT d.year + 1 AS year, d.timecode, d.weekday,
+             date(printf('%04d-01-04', d.year + 1), 'weekday 0', printf('%+d days', d.weekday - 6))
+                 AS date,
+             d.minutes, d.comment, d.entries, COALESCE(t.position, 0) AS position
+         FROM days d LEFT JOIN timecodes t USING (year, week, timecode)
+         WHERE d.week = 1
+         AND strftime('%Y', date(printf('%04d-01-04', d.year), 'weekday 0',
+             printf('%+d days', d.weekday - 6))) = printf('%04d', d.year - 1)
+         AND strftime('%Y', date(printf('%04d-01-04', d.year + 1), 'weekday 0',
+             printf('%+d days', d.weekday - 6))) = printf('%04d', d.year);
+     DELETE FROM days WHERE week = 1
+         AND (year + 1, timecode, weekday) IN (SELECT year, timecode, weekday FROM late_december);
+     DELETE FROM timecodes WHERE week = 1
+         AND (year + 1, timecode) IN (SELECT year, timecode FROM late_december)
+         AND NOT EXISTS (SELECT 1 FROM days d WHERE d.year = timecodes.year
+             AND d.week = 1 AND d.timecode = timecodes.timecode);
+     INSERT INTO timecodes (year, week, position, timecode)
+         SELECT DISTINCT year, 1, position + 1000, timecode FROM late_december WHERE true
+         ON CONFLICT DO NOTHING;
+     INSERT INTO days (year, week, timecode, weekday, date, minutes, comment, entries)
+         SELECT year, 1, timecode, weekday, date, minutes, comment, entries FROM late_december
+         WHERE true
+         ",
+        merge_days_on_conflict!(),
+        "
+     UPDATE meta SET value = json_set(value, '$.year', json_extract(value, '$.year') + 1)
+         WHERE key = 'timer' AND json_extract(value, '$.week') = 1
+         AND strftime('%Y', date(printf('%04d-01-04', json_extract(value, '$.year')), 'weekday 0',
+             printf('%+d days', json_extract(value, '$.day') - 6)))
+             = printf('%04d', json_extract(value, '$.year') - 1)
+         AND strftime('%Y', date(printf('%04d-01-04', json_extract(value, '$.year') + 1),
+             'weekday 0', printf('%+d days', json_extract(value, '$.day') - 6)))
+             = printf('%04d', json_extract(value, '$.year'));
+     DROP TABLE late_december;"
+    ),
+    // v6: absences, one per date
+    "CREATE TABLE absences (date TEXT PRIMARY KEY, kind TEXT NOT NULL, minutes INTEGER NOT NULL);",
+];
+
+const CREATE_TABLES: &str = "
+    CREATE TABLE timecodes (
+        year INTEGER NOT NULL,
+        week INTEGER NOT NULL,
+        position INTEGER NOT NULL,
+        timecode TEXT NOT NULL,
+        PRIMARY KEY (year, week, timecode)
+    );
+    CREATE TABLE days (
+        year INTEGER NOT NULL,
+        week INTEGER NOT NULL,
+        timecode TEXT NOT NULL,
+        weekday INTEGER NOT NULL,
+        date TEXT,
+        minutes INTEGER NOT NULL,
+        comment TEXT NOT NULL,
+        entries TEXT,
+        PRIMARY KEY (year, week, timecode, weekday)
+    );
+    CREATE INDEX days_by_date ON days (date);
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE absences (
+        date TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        minutes INTEGER NOT NULL
+    );
+";
+
+// Weeks and days as indexed rows, so single days can be saved and ranges queried by date
+pub struct SqliteStorage {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    pub fn open(path: PathBuf) -> Result<SqliteStorage, DataError> {
+        let conn = Connection::open(&path)?;
+        migrate(&conn)?;
+        Ok(SqliteStorage { path, conn })
+    }
+
+    pub fn open_read_only(path: PathBuf) -> Result<SqliteStorage, DataError> {
+        let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        check_version(&conn)?;
+        Ok(SqliteStorage { path, conn })
+    }
+
+    fn read_weeks(&self, filter: Option<(usize, u8)>) -> Result<TimekeeperData, DataError> {
+        let mut data = TimekeeperData::default();
+        let (year, week) = filter.unwrap_or((0, 0));
+        let all = filter.is_none();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT year, week, timecode FROM timecodes
+             WHERE ?1 OR (year = ?2 AND week = ?3)
+             ORDER BY year, week, position",
+        )?;
+        let rows = stmt.query_map(params![all, year as i64, week], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, u8>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (y, w, tc) = row?;
+            data.week_entry(y as usize, w)
+                .add_timecodes(vec![Timecode::from_string(tc)]);
+        }
+
+        let mut stmt = self.conn.prepare(
+            "SELECT year, week, timecode, weekday, minutes, comment, entries FROM days
+             WHERE ?1 OR (year = ?2 AND week = ?3)",
+        )?;
+        let rows = stmt.query_map(params![all, year as i64, week], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, u8>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, u8>(3)?,
+                r.get::<_, u32>(4)?,
+                r.get::<_, String>(5)?,
+                r.get::<_, Option<String>>(6)?,
+            ))
+        })?;
+        for row in rows {
+            let (y, w, tc, day, minutes, comment, entries) = row?;
+            let value = Day {
+                minutes,
+                comment,
+                entries: parse_entries(entries)?,
+            };
+            data.set_day(y as usize, w, &tc, day, value);
+        }
+
+        data.timer = self.read_meta("timer")?;
+        data.flex_adjustments = self.read_meta("flex_adjustments")?.unwrap_or_default();
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT date, kind, minutes FROM absences")?;
+        let rows = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, u32>(2)?,
+            ))
+        })?;
+        for row in rows {
+            let (date, kind, minutes) = row?;
+            let date = date.parse().map_err(|_| DataError::InvalidDate(date))?;
+            let kind = AbsenceKind::parse(&kind).ok_or(DataError::InvalidAbsence(kind))?;
+            data.absences.insert(date, Absence { kind, minutes });
+        }
+        Ok(data)
+    }
+
+    fn read_meta<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, DataError> {
+        let value: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", params![key], |r| {
+                r.get(0)
+            })
+            .optional()?;
+        match value {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+fn write_meta<T: Serialize>(
+    conn: &Connection,
+    key: &str,
+    value: Option<&T>,
+) -> Result<(), DataError> {
+    match value {
+        Some(v) => conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            params![key, serde_json::to_string(v)?],
+        )?,
+        None => conn.execute("DELETE FROM meta WHERE key = ?1", params![key])?,
+    };
+    Ok(())
+}
+
+impl Storage for SqliteStorage {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&mut self) -> Result<TimekeeperData, DataError> {
+        self.read_weeks(None)
+    }
+
+    fn load_week(&mut self, year: usize, week: u8) -> Result<Option<Week>, DataError> {
+        let mut data = self.read_weeks(Some((year, week)))?;
+        Ok(data.get_mut(year).and_then(|y| y.0.remove(&week)))
+    }
+
+    fn save(
+        &mut self,
+        data: &TimekeeperData,
+        weeks: &HashSet<(usize, u8)>,
+    ) -> Result<(), DataError> {
+        let tx = self.conn.transaction()?;
+        for (year, week) in weeks.iter() {
+            tx.execute(
+                "DELETE FROM timecodes WHERE year = ?1 AND week = ?2",
+                params![*year as i64, week],
+            )?;
+            tx.execute(
+                "DELETE FROM days WHERE year = ?1 AND week = ?2",
+                params![*year as i64, week],
+            )?;
+            let w = match data.get(*year).and_then(|y| y.get(*week)) {
+                Some(w) => w,
+                None => continue,
+            };
+            for (position, tc) in w.0.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO timecodes (year, week, position, timecode) VALUES (?1, ?2, ?3, ?4)",
+                    params![*year as i64, week, position as i64, tc.timecode],
+                )?;
+                for day in 0..7 {
+                    if let Some(d) = tc.get(day) {
+                        insert_day(&tx, *year, *week, &tc.timecode, day, d)?;
+                    }
+                }
+            }
+        }
+        write_meta(&tx, "timer", data.timer.as_ref())?;
+        write_meta(
+            &tx,
+            "flex_adjustments",
+            Some(&data.flex_adjustments).filter(|a| !a.is_empty()),
+        )?;
+        // Few enough to rewrite on every save
+        tx.execute("DELETE FROM absences", [])?;
+        for (date, absence) in data.absences.iter() {
+            tx.execute(
+                "INSERT INTO absences (date, kind, minutes) VALUES (?1, ?2, ?3)",
+                params![date.to_string(), absence.kind.name(), absence.minutes],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_day(
+        &mut self,
+        year: usize,
+        week: u8,
+        timecode: &str,
+        day: u8,
+        value: &Day,
+    ) -> Result<(), DataError> {
+        let tx = self.conn.transaction()?;
+        let exists = tx
+            .query_row(
+                "SELECT 1 FROM timecodes WHERE year = ?1 AND week = ?2 AND timecode = ?3",
+                params![year as i64, week, timecode],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if !exists {
+            tx.execute(
+                "INSERT INTO timecodes (year, week, position, timecode)
+                 SELECT ?1, ?2, COALESCE(MAX(position) + 1, 0), ?3 FROM timecodes
+                 WHERE year = ?1 AND week = ?2",
+                params![year as i64, week, timecode],
+            )?;
+        }
+        insert_day(&tx, year, week, timecode, day, value)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn query_range(&mut self, from: NaiveDate, to: NaiveDate) -> Result<Vec<DayRecord>, DataError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT d.date, d.timecode, d.minutes, d.comment, d.entries FROM days d
+             JOIN timecodes t USING (year, week, timecode)
+             WHERE d.date BETWEEN ?1 AND ?2
+             ORDER BY d.date, t.position",
+        )?;
+        let rows = stmt.query_map(params![from.to_string(), to.to_string()], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, u32>(2)?,
+                r.get::<_, String>(3)?,
+                r.get::<_, Option<String>>(4)?,
+            ))
+        })?;
+        let mut records = vec![];
+        for row in rows {
+            let (date, timecode, minutes, comment, entries) = row?;
+            records.push(DayRecord {
+                date: date.parse().map_err(|_| DataError::InvalidDate(date))?,
+                timecode,
+                minutes,
+                comment,
+                entries: parse_entries(entries)?,
+            });
+        }
+        Ok(records)
+    }
+}
+
+fn insert_day(
+    conn: &Connection,
+    year: usize,
+    week: u8,
+    timecode: &str,
+    day: u8,
+    value: &Day,
+) -> Result<(), DataError> {
+    // Weeks that don't exist in the ISO calendar get no date and are left out of range queries
+    let date = date_of(year, week, day).map(|d| d.to_string());
+    let entries = if value.entries.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&value.entries)?)
+    };
+    conn.execute(
+        "INSERT OR REPLACE INTO days (year, week, timecode, weekday, date, minutes, comment, entries)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            year as i64,
+            week,
+            timecode,
+            day,
+            date,
+            value.minutes,
+            value.comment,
+            entries
+        ],
+    )?;
+    Ok(())
+}
+
+fn parse_entries(entries: Option<String>) -> Result<Vec<Entry>, DataError> {
+    match entries {
+        Some(e) => Ok(serde_json::from_str(&e)?),
+        None => Ok(vec![]),
+    }
+}
+
+fn check_version(conn: &Connection) -> Result<u32, DataError> {
+    let version: u32 = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    if version > SQLITE_SCHEMA_VERSION {
+        return Err(DataError::NewerVersion {
+            found: version,
+            supported: SQLITE_SCHEMA_VERSION,
+        });
+    }
+    Ok(version)
+}
+
+fn migrate(conn: &Connection) -> Result<(), DataError> {
+    let version = check_version(conn)?;
+    if version == 0 {
+        conn.execute_batch(CREATE_TABLES)?;
+    } else {
+        for step in MIGRATIONS[version as usize - 1..].iter() {
+            conn.execute_batch(&format!("BEGIN; {} COMMIT;", step))?;
+        }
+    }
+    conn.pragma_update(None, "user_version", SQLITE_SCHEMA_VERSION)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RunningTimer;
+    use chrono::{Local, TimeZone};
+
+    // The tables as they were at version 4, before weeks were keyed by ISO week-year
+    const V4_TABLES: &str = "
+        CREATE TABLE timecodes (
+            year INTEGER NOT NULL, week INTEGER NOT NULL, position INTEGER NOT NULL,
+            timecode TEXT NOT NULL, PRIMARY KEY (year, week, timecode));
+        CREATE TABLE days (
+            year INTEGER NOT NULL, week INTEGER NOT NULL, timecode TEXT NOT NULL,
+            weekday INTEGER NOT NULL, date TEXT, minutes INTEGER NOT NULL,
+            comment TEXT NOT NULL, entries TEXT, PRIMARY KEY (year, week, timecode, weekday));
+        CREATE INDEX days_by_date ON days (date);
+        CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+        PRAGMA user_version = 4;";
+
+    // Stores the days as (year, week, timecode, weekday, minutes, comment), in order
+    fn v4_storage(days: &[(usize, u8, &str, u8, u32, &str)]) -> SqliteStorage {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(V4_TABLES).unwrap();
+        for (position, (year, week, timecode, weekday, minutes, comment)) in days.iter().enumerate()
+        {
+            conn.execute(
+                "INSERT OR IGNORE INTO timecodes (year, week, position, timecode)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![*year as i64, week, position as i64, timecode],
+            )
+            .unwrap();
+            let mut day = Day::new(*minutes);
+            day.comment = comment.to_string();
+            insert_day(&conn, *year, *week, timecode, *weekday, &day).unwrap();
+        }
+        SqliteStorage {
+            path: PathBuf::new(),
+            conn,
+        }
+    }
+
+    fn day(
+        data: &TimekeeperData,
+        year: usize,
+        week: u8,
+        tc: &str,
+        day: u8,
+    ) -> Option<(u32, String)> {
+        let week = data.get(year)?.get(week)?;
+        let d = week.0.iter().find(|t| t.timecode == tc)?.get(day)?;
+        Some((d.minutes, d.comment.clone()))
+    }
+
+    #[test]
+    fn weeks_filed_by_calendar_year_are_moved() {
+        let mut storage = v4_storage(&[
+            (2024, 1, "ACME", 0, 60, "Jan 1"),
+            (2025, 1, "ACME", 0, 120, "Dec 29"),
+            (2025, 1, "ACME", 2, 60, "Jan 1"),
+            (2025, 1, "Support", 1, 30, "Dec 30"),
+            (2026, 1, "Internal", 3, 45, ""),
+            (2026, 53, "ACME", 4, 30, "x"),
+            (2027, 53, "ACME", 4, 60, "new year"),
+            (2027, 53, "ACME", 5, 15, ""),
+        ]);
+        let timer = RunningTimer {
+            year: 2025,
+            week: 1,
+            timecode: String::from("Support"),
+            day: 1,
+            started: Local.ymd(2025, 12, 30).and_hms(9, 0, 0),
+        };
+        write_meta(&storage.conn, "timer", Some(&timer)).unwrap();
+        migrate(&storage.conn).unwrap();
+        let data = storage.load().unwrap();
+
+        // Jan 1 2027 belongs to week 53 of 2026, merging with the day logged there
+        assert!(data.get(2027).and_then(|y| y.get(53)).is_none());
+        let friday = day(&data, 2026, 53, "ACME", 4);
+        assert_eq!(friday, Some((90, String::from("x; new year"))));
+        assert_eq!(day(&data, 2026, 53, "ACME", 5), Some((15, String::new())));
+
+        // Dec 29 and 30 2025 belong to week 1 of 2026, after the timecodes already there
+        let week: Vec<&str> = data
+            .get(2026)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .0
+            .iter()
+            .map(|tc| tc.timecode.as_str())
+            .collect();
+        assert_eq!(week, ["Internal", "ACME", "Support"]);
+        assert_eq!(
+            day(&data, 2026, 1, "ACME", 0),
+            Some((120, String::from("Dec 29")))
+        );
+        assert_eq!(
+            day(&data, 2026, 1, "Support", 1),
+            Some((30, String::from("Dec 30")))
+        );
+        assert_eq!(day(&data, 2025, 1, "ACME", 0), None);
+        assert_eq!(
+            day(&data, 2025, 1, "ACME", 2),
+            Some((60, String::from("Jan 1")))
+        );
+        assert!(data
+            .get(2025)
+            .unwrap()
+            .get(1)
+            .unwrap()
+            .0
+            .iter()
+            .all(|tc| tc.timecode != "Support"));
+        assert_eq!(data.timer.as_ref().map(|t| t.year), Some(2026));
+
+        // Jan 1 2024 and Dec 30 2024 shared this slot, so it stays
+        assert_eq!(
+            day(&data, 2024, 1, "ACME", 0),
+            Some((60, String::from("Jan 1")))
+        );
+
+        // Moved days are found by their real date
+        let dec_29 = NaiveDate::from_ymd(2025, 12, 29);
+        let records = storage.query_range(dec_29, dec_29).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].comment, "Dec 29");
+        let jan_1 = NaiveDate::from_ymd(2027, 1, 1);
+        let records = storage.query_range(jan_1, jan_1).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].minutes, 90);
+    }
+
+    #[test]
+    fn colliding_days_with_and_without_entries_keep_all_hours() {
+        let mut storage =
+            v4_storage(&[(2026, 53, "ACME", 5, 30, ""), (2027, 53, "ACME", 4, 90, "")]);
+        let timed = |start: &str, end: &str| {
+            let mut day = Day::new(0);
+            day.entries = vec![Entry::parse(&format!("{}-{}", start, end)).unwrap()];
+            day.sync_minutes();
+            day
+        };
+        insert_day(&storage.conn, 2026, 53, "ACME", 4, &timed("09:00", "10:00")).unwrap();
+        insert_day(&storage.conn, 2027, 53, "ACME", 5, &timed("08:00", "09:00")).unwrap();
+        migrate(&storage.conn).unwrap();
+        let data = storage.load().unwrap();
+
+        let tc = &data.get(2026).unwrap().get(53).unwrap().0[0];
+        let ranges = |day: u8| -> Vec<String> {
+            let d = tc.get(day).unwrap();
+            d.entries
+                .iter()
+                .map(|e| e.to_string().trim().to_string())
+                .collect()
+        };
+        assert_eq!(tc.get(4).unwrap().minutes, 150);
+        assert_eq!(ranges(4), ["09:00-10:00", "10:00-11:30"]);
+        assert_eq!(tc.get(5).unwrap().minutes, 90);
+        assert_eq!(ranges(5), ["08:00-09:00", "09:00-09:30"]);
+    }
+
+    #[test]
+    fn late_december_days_move_one_year_only() {
+        let mut storage = v4_storage(&[
+            (2013, 1, "ACME", 0, 60, "Dec 30 2013"),
+            (2014, 1, "ACME", 0, 30, "Dec 29 2014"),
+        ]);
+        migrate(&storage.conn).unwrap();
+        let data = storage.load().unwrap();
+        let dec_2013 = Some((60, String::from("Dec 30 2013")));
+        assert_eq!(day(&data, 2014, 1, "ACME", 0), dec_2013);
+        let dec_2014 = Some((30, String::from("Dec 29 2014")));
+        assert_eq!(day(&data, 2015, 1, "ACME", 0), dec_2014);
+    }
+}
@@ -0,0 +1,62 @@
+use crate::app::App;
+use crate::i18n::I18n;
+
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::Span;
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use tui::Frame;
+
+// Screen listing backups of the data file
+pub fn draw_backup_screen<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let t_width = f.size().width;
+
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(100)].as_ref())
+        .split(f.size());
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(I18n::backups_label(&app.conf.lang));
+
+    if app.backups.is_empty() {
+        let p = Paragraph::new(I18n::no_backups(&app.conf.lang)).block(block);
+        f.render_widget(p, main_layout[0]);
+        return;
+    }
+
+    let rows: Vec<Row> = app
+        .backups
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            Row::new(vec![Cell::from(Span::raw(name))])
+        })
+        .collect();
+
+    let cols = [Constraint::Length(t_width)];
+
+    let backup_table = Table::new(rows)
+        .style(Style::default())
+        .block(block)
+        .highlight_style(
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        )
+        .widths(&cols);
+
+    // Stateful rendering scrolls the list to keep the selection visible
+    let mut state = TableState::default();
+    state.select(Some(app.active_backup));
+    f.render_stateful_widget(backup_table, main_layout[0], &mut state);
+}
@@ -24,7 +24,7 @@ where
             ),
         ]
     } else {
-        vec![Span::styled(String::from(comment), Style::default())]
+        vec![Span::styled(comment, Style::default())]
     });
     let comment_box = Paragraph::new(styled_comment)
         .style(Style::default().fg(Color::Yellow))
@@ -40,7 +40,7 @@ where
 
     let rows: Vec<Row> = key_strings
         .into_iter()
-        .zip(action_strings.into_iter())
+        .zip(action_strings)
         .map(|(a, b)| Row::new(vec![Cell::from(a), Cell::from(b)]))
         .collect();
 
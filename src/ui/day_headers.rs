@@ -1,5 +1,7 @@
 use crate::app::App;
+use crate::data::date_of;
 use crate::i18n::I18n;
+use crate::ui::HOLIDAY_COLOR;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Modifier, Style};
@@ -33,20 +35,22 @@ where
         .split(*layout);
 
     let to_span = |a| Span::styled(a, Style::default());
-    let header = I18n::day_labels(&app.conf.lang)
-        .into_iter()
-        .map(|s| to_span(s));
+    let header = I18n::day_labels(&app.conf.lang).into_iter().map(to_span);
 
     for (idx, day) in header.into_iter().enumerate() {
-        let d_header = if app.active_day == idx as u8 {
-            Paragraph::new(day).block(
-                Block::default()
-                    .style(Style::default().add_modifier(Modifier::BOLD))
-                    .borders(Borders::ALL),
-            )
-        } else {
-            Paragraph::new(day).block(Block::default().borders(Borders::ALL))
-        };
+        let mut block = Block::default().borders(Borders::ALL);
+        if app.active_day == idx as u8 {
+            block = block.style(Style::default().add_modifier(Modifier::BOLD));
+        }
+        let mut d_header = Paragraph::new(day);
+        // Holidays are named in the border
+        let holiday =
+            date_of(app.active_year, app.active_week, idx as u8).and_then(|d| app.conf.holiday(d));
+        if let Some(name) = holiday {
+            block = block.title(Span::styled(name, Style::default().fg(HOLIDAY_COLOR)));
+            d_header = d_header.style(Style::default().fg(HOLIDAY_COLOR));
+        }
+        let d_header = d_header.block(block);
         f.render_widget(d_header, day_header_layout[idx + 1]);
     }
 
@@ -1,12 +1,14 @@
-use crate::app::App;
-use crate::data::Day;
+use crate::app::{App, State};
+use crate::duration::format_minutes;
+use crate::hierarchy::Row;
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
-use tui::style::{Modifier, Style};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
 use tui::widgets::{Block, BorderType, Borders, Paragraph};
 use tui::Frame;
 
-pub fn draw_days<B>(f: &mut Frame<B>, app: &App, content_layout: &Vec<Rect>)
+pub fn draw_days<B>(f: &mut Frame<B>, app: &App, content_layout: &[Rect])
 where
     B: Backend,
 {
@@ -31,51 +33,67 @@ where
 
     let week = app.get_active_week().expect("ERR: Active week not found!");
 
-    let mut codes = vec![];
-    let mut days = vec![vec![]; 7];
-
-    let mut handle = |day: &Option<Day>, day_idx: usize| match day {
-        Some(m) => days[day_idx].push(m.hours),
-        None => days[day_idx].push(-1.0_f32),
-    };
-
-    for (_idx, tc) in week.0.iter().enumerate() {
-        codes.push(tc.timecode.clone());
-        handle(&tc.monday, 0);
-        handle(&tc.tuesday, 1);
-        handle(&tc.wednesday, 2);
-        handle(&tc.thursday, 3);
-        handle(&tc.friday, 4);
-        handle(&tc.saturday, 5);
-        handle(&tc.sunday, 6);
-    }
-
     let start = app.timecode_range[0];
     let end = app.timecode_range[1];
 
     // For day in week
-    for idx in 0..7 {
-        // For timecode per day
-        for (tc_idx, d) in days[idx][start..end].iter().enumerate() {
-            let offset_idx = tc_idx + start;
-            let (block, style) =
-                if (offset_idx) == app.active_timecode && idx as u8 == app.active_day {
-                    (
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .border_type(BorderType::Thick),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    )
-                } else {
-                    (Block::default().borders(Borders::ALL), Style::default())
-                };
-            if *d < 0.0 {
-                let p = Paragraph::new("").block(block).style(style);
-                f.render_widget(p, day_layouts[idx][tc_idx]);
+    for (idx, day_layout) in day_layouts.iter().enumerate() {
+        // For row per day
+        for (row_idx, row) in app.rows[start..end].iter().enumerate() {
+            let offset_idx = row_idx + start;
+            let is_active = offset_idx == app.active_row && idx as u8 == app.active_day;
+            let (block, mut style) = if is_active {
+                (
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Thick),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )
             } else {
-                let p = Paragraph::new(d.to_string()).block(block).style(style);
-                f.render_widget(p, day_layouts[idx][tc_idx]);
-            }
+                (Block::default().borders(Borders::ALL), Style::default())
+            };
+            let text = if is_active && app.get_state() == &State::EnteringDuration {
+                Spans::from(vec![
+                    Span::styled(
+                        app.duration_buffer.clone(),
+                        Style::default().fg(Color::LightYellow),
+                    ),
+                    Span::styled(
+                        String::from("|"),
+                        Style::default().add_modifier(Modifier::SLOW_BLINK),
+                    ),
+                ])
+            } else {
+                let mut text = match row.day_minutes(week, idx as u8) {
+                    Some(minutes) => format_minutes(minutes, app.conf.duration_format),
+                    None => String::from(""),
+                };
+                match row {
+                    // Subtotals of the group's timecodes
+                    Row::Group { .. } => style = style.fg(Color::Cyan),
+                    Row::Timecode { idx: tc_idx, .. } => {
+                        if is_timed(app, &week.0[*tc_idx].timecode, idx as u8) {
+                            text.push_str(" ⏱");
+                        }
+                    }
+                }
+                Spans::from(text)
+            };
+            let p = Paragraph::new(text).block(block).style(style);
+            f.render_widget(p, day_layout[row_idx]);
+        }
+    }
+}
+
+// Whether the running stopwatch belongs to this cell of the shown week
+fn is_timed(app: &App, timecode: &str, day: u8) -> bool {
+    match &app.data.timer {
+        Some(t) => {
+            t.year == app.active_year
+                && t.week == app.active_week
+                && t.timecode == timecode
+                && t.day == day
         }
+        None => false,
     }
 }
@@ -0,0 +1,107 @@
+use crate::app::{App, State};
+use crate::duration::format_minutes;
+use crate::i18n::I18n;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use tui::Frame;
+
+// Popup with the time entries of the active cell
+pub fn draw_entries<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let area = centered_rect(60, 50, f.size());
+    f.render_widget(Clear, area);
+
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let items: Vec<ListItem> = app
+        .get_active_entries()
+        .iter()
+        .map(|e| {
+            ListItem::new(Spans::from(vec![
+                Span::styled(
+                    format!("{}-{} ", e.start.format("%H:%M"), e.end.format("%H:%M")),
+                    Style::default().fg(Color::Magenta),
+                ),
+                Span::styled(
+                    format!(
+                        "({}) ",
+                        format_minutes(e.minutes(), app.conf.duration_format)
+                    ),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(e.note.clone()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(I18n::entries_label(&app.conf.lang)),
+        )
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+
+    let mut state = ListState::default();
+    if app.get_state() == &State::EditingEntries && !app.get_active_entries().is_empty() {
+        state.select(Some(app.active_entry));
+    }
+    f.render_stateful_widget(list, popup_layout[0], &mut state);
+
+    let input = if app.get_state() == &State::EditingEntry {
+        Spans::from(vec![
+            Span::styled(
+                app.entry_buffer.clone(),
+                Style::default().fg(Color::LightYellow),
+            ),
+            Span::styled(
+                String::from("|"),
+                Style::default().add_modifier(Modifier::SLOW_BLINK),
+            ),
+        ])
+    } else {
+        Spans::from("")
+    };
+    let input_box = Paragraph::new(input).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(I18n::entry_input_label(&app.conf.lang)),
+    );
+    f.render_widget(input_box, popup_layout[1]);
+}
+
+// Rect of the given size, in percent, centered in `r`
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
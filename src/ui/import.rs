@@ -0,0 +1,102 @@
+use crate::app::App;
+use crate::duration::format_minutes;
+use crate::i18n::I18n;
+
+use chrono::Datelike;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use tui::Frame;
+
+// Screen listing the calendar events to be imported, each of which can be left out
+pub fn draw_review_screen<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let lang = &app.conf.lang;
+    let fmt = app.conf.duration_format;
+
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(3)].as_ref())
+        .split(f.size());
+
+    let day_labels = I18n::day_labels(lang);
+    let rows: Vec<Row> = app
+        .proposals
+        .iter()
+        .map(|p| {
+            let weekday: String = day_labels[p.date.weekday().num_days_from_monday() as usize]
+                .chars()
+                .take(3)
+                .collect();
+            let time = match p.time {
+                Some((start, end)) => format!("{}-{}", start.format("%H:%M"), end.format("%H:%M")),
+                None => I18n::all_day_label(lang).to_string(),
+            };
+            let summary = if p.duplicate {
+                format!("{} ({})", p.summary, I18n::duplicate_label(lang))
+            } else {
+                p.summary.clone()
+            };
+            let style = if p.selected {
+                Style::default()
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Row::new(vec![
+                Cell::from(if p.selected { "[x]" } else { "[ ]" }),
+                Cell::from(format!("{} {}", weekday, p.date)),
+                Cell::from(time),
+                Cell::from(summary),
+                Cell::from(p.timecode.clone()),
+                Cell::from(format_minutes(p.minutes, fmt)),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let width = main_layout[0].width.saturating_sub(2);
+    let cols = [
+        Constraint::Length(3),
+        Constraint::Length(14),
+        Constraint::Length(11),
+        Constraint::Length(width.saturating_sub(3 + 14 + 11 + 8 + 5) * 3 / 5),
+        Constraint::Length(width.saturating_sub(3 + 14 + 11 + 8 + 5) * 2 / 5),
+        Constraint::Length(8),
+    ];
+    let mut title = I18n::review_label(lang).to_string();
+    if app.unmatched_events > 0 {
+        title.push_str(&format!(
+            " - {}",
+            I18n::unmatched_events(lang, app.unmatched_events)
+        ));
+    }
+    if app.unsupported_events > 0 {
+        title.push_str(&format!(
+            " - {}",
+            I18n::unsupported_events(lang, app.unsupported_events)
+        ));
+    }
+    let table = Table::new(rows)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .widths(&cols);
+
+    // Stateful rendering scrolls the list to keep the selection visible
+    let mut state = TableState::default();
+    state.select(Some(app.active_proposal));
+    f.render_stateful_widget(table, main_layout[0], &mut state);
+
+    let selected = app.proposals.iter().filter(|p| p.selected);
+    let text = I18n::review_help(
+        lang,
+        selected.clone().count(),
+        &format_minutes(selected.map(|p| p.minutes).sum(), fmt),
+    );
+    let info = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(info, main_layout[1]);
+}
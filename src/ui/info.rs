@@ -1,4 +1,5 @@
-use crate::app::App;
+use crate::app::{App, State};
+use crate::duration::format_minutes;
 use crate::i18n::I18n;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
@@ -10,38 +11,87 @@ pub fn draw_info<B>(f: &mut Frame<B>, app: &App, layout: &Rect)
 where
     B: Backend,
 {
-    // TODO: Expand to show various info to user (error messages, saved status etc)
-    if !app.conf.has_seen_info {
-        let t_width = f.size().width;
-
-        let info_layout = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints(
-                [
-                    Constraint::Percentage(15),
-                    Constraint::Length(t_width * 6 / 10),
-                    Constraint::Percentage(15),
-                ]
-                .as_ref(),
+    // Messages from the last action take precedence over the controls hint
+    let text = match &app.message {
+        Some(m) => m.clone(),
+        None if app.get_state() == &State::Recovering => {
+            I18n::recovery_prompt(&app.conf.lang, app.recovered_edits)
+        }
+        None if app.get_state() == &State::Locked => I18n::lock_prompt(
+            &app.conf.lang,
+            app.lock_owner.as_deref().unwrap_or_default(),
+        ),
+        None if app.get_state() == &State::RenamingTimecode => format!(
+            "{}{}|",
+            I18n::rename_prompt(&app.conf.lang, &app.get_cur_timecode().unwrap_or_default()),
+            app.timecode_buffer
+        ),
+        None if app.get_state() == &State::EnteringAbsence => format!(
+            "{}{}|",
+            I18n::absence_prompt(&app.conf.lang),
+            app.absence_buffer
+        ),
+        None if app.get_state() == &State::EnteringCalendarPath => format!(
+            "{}{}|",
+            I18n::calendar_prompt(&app.conf.lang),
+            app.calendar_buffer
+        ),
+        None if app.get_state() == &State::ConfirmingDelete => I18n::delete_prompt(
+            &app.conf.lang,
+            &app.get_cur_timecode().unwrap_or_default(),
+            &format_minutes(
+                app.get_active_week()
+                    .and_then(|w| w.get(app.active_timecode))
+                    .map(|tc| tc.total_minutes())
+                    .unwrap_or(0),
+                app.conf.duration_format,
+            ),
+        ),
+        None if app.get_state() == &State::ExternalChange => {
+            I18n::external_change_prompt(&app.conf.lang).to_string()
+        }
+        None if app.data.timer.is_some() => {
+            let timer = app.data.timer.as_ref().unwrap();
+            let secs = timer.elapsed().num_seconds().max(0);
+            format!(
+                "⏱ {} {}:{:02}:{:02}",
+                timer.timecode,
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
             )
-            .split(*layout);
+        }
+        None if !app.conf.has_seen_info => I18n::info_screen(&app.conf.lang).to_string(),
+        None => return,
+    };
 
-        // Bør kunne vises og skjules
-        let info = Paragraph::new(I18n::info_screen(&app.conf.lang))
-            .alignment(Alignment::Center)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Info")
-                    .border_type(BorderType::Plain),
-            );
-        f.render_widget(info, info_layout[1]);
+    let t_width = f.size().width;
 
-        if (app.timecode_range[1]) < app.timecodes.len() {
-            let down_arrow = Paragraph::new("↓")
-                .block(Block::default())
-                .alignment(Alignment::Center);
-            f.render_widget(down_arrow, info_layout[0]);
-        }
+    let info_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(15),
+                Constraint::Length(t_width * 6 / 10),
+                Constraint::Percentage(15),
+            ]
+            .as_ref(),
+        )
+        .split(*layout);
+
+    // Bør kunne vises og skjules
+    let info = Paragraph::new(text).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Info")
+            .border_type(BorderType::Plain),
+    );
+    f.render_widget(info, info_layout[1]);
+
+    if (app.timecode_range[1]) < app.rows.len() {
+        let down_arrow = Paragraph::new("↓")
+            .block(Block::default())
+            .alignment(Alignment::Center);
+        f.render_widget(down_arrow, info_layout[0]);
     }
 }
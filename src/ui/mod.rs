@@ -1,23 +1,37 @@
+mod backups;
 mod comment;
 mod controls;
 mod day_headers;
 mod days;
+mod entries;
+mod import;
 mod info;
+mod reports;
 mod tc_labels;
 mod top_bar;
+mod totals;
 
+use self::backups::draw_backup_screen;
 use self::comment::draw_comment;
 use self::controls::draw_control_screen;
 use self::days::draw_days;
+use self::entries::draw_entries;
+use self::import::draw_review_screen;
 use self::info::draw_info;
+use self::reports::draw_reports_screen;
 use crate::app::{App, State};
 use crate::ui::day_headers::draw_day_headers;
 use crate::ui::tc_labels::draw_timecode_labels;
 use crate::ui::top_bar::draw_top_bar;
+use crate::ui::totals::draw_totals;
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout};
+use tui::style::Color;
 use tui::Frame;
 
+// Days off, in the day headers and the dates above them
+pub const HOLIDAY_COLOR: Color = Color::LightRed;
+
 pub fn draw_main_layout<B>(f: &mut Frame<B>, app: &App)
 where
     B: Backend,
@@ -28,6 +42,21 @@ where
         draw_control_screen(f, app);
         return;
     }
+    if app.get_state() == &State::RestoringBackup {
+        draw_backup_screen(f, app);
+        return;
+    }
+    if matches!(
+        app.get_state(),
+        State::Reports | State::EnteringReportRange | State::EnteringExportPath
+    ) {
+        draw_reports_screen(f, app);
+        return;
+    }
+    if app.get_state() == &State::ReviewingImport {
+        draw_review_screen(f, app);
+        return;
+    }
 
     // Main layout
     let main_layout = Layout::default()
@@ -38,6 +67,7 @@ where
                 Constraint::Length(3),
                 Constraint::Min(5),
                 Constraint::Length(3),
+                Constraint::Length(3),
             ]
             .as_ref(),
         )
@@ -67,5 +97,10 @@ where
     draw_day_headers(f, app, &main_layout[1]);
     draw_days(f, app, &content_layout);
     draw_comment(f, app, &content_layout[8]);
-    draw_info(f, app, &main_layout[3]);
+    draw_totals(f, app, &main_layout[3]);
+    draw_info(f, app, &main_layout[4]);
+
+    if matches!(app.get_state(), State::EditingEntries | State::EditingEntry) {
+        draw_entries(f, app);
+    }
 }
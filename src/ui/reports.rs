@@ -0,0 +1,219 @@
+use crate::app::{App, State};
+use crate::duration::format_minutes;
+use crate::i18n::I18n;
+use crate::report::Breakdown;
+
+use chrono::Datelike;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{BarChart, Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use tui::Frame;
+
+// Screen with hours summed per timecode, client and day over a range of dates
+pub fn draw_reports_screen<B>(f: &mut Frame<B>, app: &App)
+where
+    B: Backend,
+{
+    let report = match &app.report {
+        Some(r) => r,
+        None => return,
+    };
+    let lang = &app.conf.lang;
+    let fmt = app.conf.duration_format;
+
+    let main_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(3),
+                Constraint::Min(6),
+                Constraint::Percentage(40),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let header = Paragraph::new(Spans::from(vec![
+        Span::styled(
+            format!(
+                "{}: {} ",
+                I18n::reports_label(lang),
+                I18n::period_label(lang, report.period)
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(format!("{} - {}   ", report.from, report.to)),
+        Span::styled(
+            format!("Σ {}", format_minutes(report.total, fmt)),
+            Style::default().fg(Color::Cyan),
+        ),
+    ]))
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, main_layout[0]);
+
+    let lists_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(40),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+            ]
+            .as_ref(),
+        )
+        .split(main_layout[1]);
+
+    let share = |minutes: u32| {
+        if report.total == 0 {
+            String::from("")
+        } else {
+            format!(
+                "{}%",
+                (minutes as f64 * 100.0 / report.total as f64).round()
+            )
+        }
+    };
+    let total_rows = |totals: &[(String, u32)]| -> Vec<Row> {
+        totals
+            .iter()
+            .map(|(label, minutes)| {
+                let label = if label.is_empty() {
+                    I18n::no_client(lang)
+                } else {
+                    label.as_str()
+                };
+                Row::new(vec![
+                    Cell::from(label.to_string()),
+                    Cell::from(format_minutes(*minutes, fmt)),
+                    Cell::from(share(*minutes)),
+                ])
+            })
+            .collect()
+    };
+    draw_list(
+        f,
+        I18n::breakdown_label(lang, Breakdown::Timecodes),
+        total_rows(&report.timecodes),
+        None,
+        lists_layout[0],
+    );
+    draw_list(
+        f,
+        I18n::breakdown_label(lang, Breakdown::Clients),
+        total_rows(&report.clients),
+        None,
+        lists_layout[1],
+    );
+
+    let day_labels = I18n::day_labels(lang);
+    let day_rows: Vec<Row> = report
+        .days
+        .iter()
+        .map(|(date, minutes)| {
+            let weekday: String = day_labels[date.weekday().num_days_from_monday() as usize]
+                .chars()
+                .take(3)
+                .collect();
+            Row::new(vec![
+                Cell::from(format!("{} {}", weekday, date)),
+                Cell::from(format_minutes(*minutes, fmt)),
+                Cell::from(share(*minutes)),
+            ])
+        })
+        .collect();
+    draw_list(
+        f,
+        I18n::day_label(lang),
+        day_rows,
+        Some(app.active_report_day),
+        lists_layout[2],
+    );
+
+    let totals = match app.report_breakdown {
+        Breakdown::Timecodes => &report.timecodes,
+        Breakdown::Clients => &report.clients,
+    };
+    // Bars show whole hours, the lists above have the exact durations
+    let bars: Vec<(&str, u64)> = totals
+        .iter()
+        .map(|(label, minutes)| {
+            let label = if label.is_empty() {
+                I18n::no_client(lang)
+            } else {
+                label.as_str()
+            };
+            (label, (*minutes as u64 + 30) / 60)
+        })
+        .collect();
+    let chart_area = main_layout[2];
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(I18n::breakdown_label(lang, app.report_breakdown));
+    if bars.is_empty() {
+        let p = Paragraph::new(I18n::no_hours(lang))
+            .alignment(Alignment::Center)
+            .block(block);
+        f.render_widget(p, chart_area);
+    } else {
+        // As wide as fits, largest first, so any bars cut off at the edge are the smallest
+        let inner = chart_area.width.saturating_sub(2);
+        let bar_width = (inner / bars.len() as u16).saturating_sub(1).clamp(3, 16);
+        let chart = BarChart::default()
+            .block(block)
+            .data(&bars)
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::Cyan))
+            .value_style(
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            );
+        f.render_widget(chart, chart_area);
+    }
+
+    let text = match &app.message {
+        Some(m) => m.clone(),
+        None if app.get_state() == &State::EnteringReportRange => {
+            format!("{}{}|", I18n::range_prompt(lang), app.report_buffer)
+        }
+        None if app.get_state() == &State::EnteringExportPath => {
+            format!("{}{}|", I18n::export_prompt(lang), app.export_buffer)
+        }
+        None => I18n::reports_help(lang).to_string(),
+    };
+    let info = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(info, main_layout[3]);
+}
+
+// A table of labels, durations and their share of the total
+fn draw_list<B>(f: &mut Frame<B>, title: &str, rows: Vec<Row>, selected: Option<usize>, area: Rect)
+where
+    B: Backend,
+{
+    let width = area.width.saturating_sub(2);
+    let cols = [
+        Constraint::Length(width.saturating_sub(16)),
+        Constraint::Length(8),
+        Constraint::Length(6),
+    ];
+    let table = Table::new(rows)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title.to_string()),
+        )
+        .highlight_style(Style::default().fg(Color::Magenta))
+        .widths(&cols);
+
+    // Stateful rendering scrolls the list to keep the selection visible
+    let mut state = TableState::default();
+    state.select(selected);
+    f.render_stateful_widget(table, area, &mut state);
+}
@@ -1,4 +1,6 @@
 use crate::app::{App, State};
+use crate::duration::format_minutes;
+use crate::hierarchy::Row;
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
@@ -35,24 +37,74 @@ where
     let end = app.timecode_range[1];
 
     let week = app.get_active_week().expect("ERR: Active week not found!");
-    for (idx, tc) in week.0[start..end].iter().enumerate() {
+    for (idx, row) in app.rows[start..end].iter().enumerate() {
         let offset_idx = idx + start;
-        let style = if app.starred_timecodes.contains(&app.timecodes[offset_idx]) {
-            fav_style
-        } else {
-            Style::default()
+        let is_active = app.active_row == offset_idx && app.get_state() != &State::AddingTimecode;
+        let indent = "  ".repeat(row.depth());
+        let (text, style) = match row {
+            Row::Group {
+                label, collapsed, ..
+            } => {
+                let mut code_style = Style::default().fg(Color::Cyan);
+                if is_active {
+                    code_style = code_style.add_modifier(Modifier::BOLD);
+                }
+                let marker = if *collapsed { "▸" } else { "▾" };
+                let text = vec![
+                    Spans::from(Span::styled(
+                        format!("{}{} {}", indent, marker, label),
+                        code_style,
+                    )),
+                    // Subtotal for the week
+                    Spans::from(Span::styled(
+                        format!(
+                            "{}  Σ {}",
+                            indent,
+                            format_minutes(row.week_minutes(week), app.conf.duration_format)
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                ];
+                (text, Style::default())
+            }
+            Row::Timecode {
+                idx: tc_idx, label, ..
+            } => {
+                let tc = &week.0[*tc_idx];
+                let style = if app.starred_timecodes.contains(&tc.timecode) {
+                    fav_style
+                } else {
+                    Style::default()
+                };
+                let info = app.conf.timecode_info(&tc.timecode);
+
+                let mut code_style = Style::default();
+                if let Some(color) = info.and_then(|i| i.color.as_deref()).and_then(parse_color) {
+                    code_style = code_style.fg(color);
+                }
+                if info.map(|i| i.archived).unwrap_or(false) {
+                    code_style = code_style.add_modifier(Modifier::DIM);
+                }
+                if is_active {
+                    code_style = code_style.add_modifier(Modifier::BOLD);
+                }
+                let mut text = vec![Spans::from(Span::styled(
+                    format!("{}{}", indent, label),
+                    code_style,
+                ))];
+                if let Some(i) = info.filter(|i| !i.description.is_empty()) {
+                    text.push(Spans::from(Span::styled(
+                        format!("{}{}", indent, i.description),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                (text, style)
+            }
         };
-        let tc_str =
-            if app.active_timecode == (offset_idx) && app.get_state() != &State::AddingTimecode {
-                Paragraph::new(tc.timecode.clone())
-                    .wrap(Wrap { trim: true })
-                    .style(Style::default().add_modifier(Modifier::BOLD))
-                    .block(Block::default().borders(Borders::ALL).border_style(style))
-            } else {
-                Paragraph::new(tc.timecode.clone())
-                    .wrap(Wrap { trim: true })
-                    .block(Block::default().borders(Borders::ALL).border_style(style))
-            };
+
+        let tc_str = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).border_style(style));
         f.render_widget(tc_str, tc_layout[idx])
     }
 
@@ -75,3 +127,38 @@ where
         )
     }
 }
+
+// Colors from the timecode registry: a name like "blue" or "light-red", or "#rrggbb"
+fn parse_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+    let color = match color
+        .to_lowercase()
+        .replace(&['-', '_', ' '][..], "")
+        .as_str()
+    {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+    Some(color)
+}
@@ -1,9 +1,13 @@
 use crate::app::App;
+use crate::data::date_of;
+use crate::duration::format_minutes;
 use crate::i18n::I18n;
+use crate::ui::totals::target_style;
+use crate::ui::HOLIDAY_COLOR;
 use chrono::Datelike;
 use tui::backend::Backend;
 use tui::layout::{Constraint, Direction, Layout, Rect};
-use tui::style::{Modifier, Style};
+use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{Block, Borders, Paragraph, Wrap};
 use tui::Frame;
@@ -39,23 +43,59 @@ where
             Style::default(),
         ),
         Span::styled(app.active_year.to_string(), Style::default()),
+        if app.dirty {
+            Span::styled(" ●", Style::default().fg(Color::Yellow))
+        } else {
+            Span::styled(" ✓", Style::default().fg(Color::Green))
+        },
     ]);
     let year_week_p = Paragraph::new(year_week_spans)
         .wrap(Wrap { trim: true })
         .block(Block::default().borders(Borders::ALL));
     f.render_widget(year_week_p, top_bar_layout[0]);
 
+    // RENDER: Week total against the weekly target, absences counting as worked
+    let absent: u32 = (0..7)
+        .filter_map(|day| date_of(app.active_year, app.active_week, day))
+        .map(|d| app.data.absence_minutes(d))
+        .sum();
+    let total = app
+        .get_active_week()
+        .map(|w| w.total_minutes())
+        .unwrap_or(0)
+        + absent;
+    let target = app.conf.weekly_target(app.active_year, app.active_week);
+    let mut total_text = format!("Σ {}", format_minutes(total, app.conf.duration_format));
+    if target > 0 {
+        total_text.push_str(&format!(
+            " / {}",
+            format_minutes(target, app.conf.duration_format)
+        ));
+    }
+    let total_p = Paragraph::new(total_text)
+        .style(target_style(
+            total,
+            target,
+            date_of(app.active_year, app.active_week, 6),
+        ))
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(total_p, top_bar_layout[8]);
+
     let mut weekday = chrono::Weekday::Mon;
     for idx in 0..7 {
         let day =
             chrono::NaiveDate::from_isoywd(app.active_year as i32, app.active_week as u32, weekday);
+        let mut style = if idx as u8 == app.active_day {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        if app.conf.holiday(day).is_some() {
+            style = style.fg(HOLIDAY_COLOR);
+        }
         let d = Paragraph::new(format!("{}/{}", day.day(), day.month()))
             .wrap(Wrap { trim: true })
-            .style(if idx as u8 == app.active_day {
-                Style::default().add_modifier(Modifier::BOLD)
-            } else {
-                Style::default()
-            })
+            .style(style)
             .block(Block::default().borders(Borders::ALL));
 
         f.render_widget(d, top_bar_layout[1 + idx]);
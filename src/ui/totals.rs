@@ -0,0 +1,105 @@
+use crate::app::App;
+use crate::data::date_of;
+use crate::duration::format_minutes;
+use crate::flex;
+use crate::i18n::I18n;
+use chrono::{Local, NaiveDate};
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph};
+use tui::Frame;
+
+pub fn draw_totals<B>(f: &mut Frame<B>, app: &App, layout: &Rect)
+where
+    B: Backend,
+{
+    let t_width = f.size().width;
+
+    let totals_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage(15),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Length(t_width / 10),
+                Constraint::Percentage(15),
+            ]
+            .as_ref(),
+        )
+        .split(*layout);
+
+    let label = Paragraph::new(I18n::total_label(&app.conf.lang))
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(label, totals_layout[0]);
+
+    let week = match app.get_active_week() {
+        Some(w) => w,
+        None => return,
+    };
+    for day in 0..7 {
+        let date = date_of(app.active_year, app.active_week, day);
+        let absence = date.and_then(|d| app.data.absences.get(&d));
+        // Absences count towards the target, and name the day they're on
+        let minutes = week.day_minutes(day) + absence.map(|a| a.minutes).unwrap_or(0);
+        let target = date.map(|d| app.conf.daily_target(d)).unwrap_or(0);
+        let text = if minutes > 0 {
+            format_minutes(minutes, app.conf.duration_format)
+        } else {
+            String::from("")
+        };
+        let mut block = Block::default().borders(Borders::ALL);
+        if let Some(a) = absence {
+            block = block.title(Span::styled(
+                I18n::absence_label(&app.conf.lang, a.kind),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        let p = Paragraph::new(text)
+            .style(target_style(minutes, target, date))
+            .block(block);
+        f.render_widget(p, totals_layout[1 + day as usize]);
+    }
+
+    // Flex balance up to today
+    let today = Local::now().naive_local().date();
+    if let Some(balance) = flex::balance(&app.data, &app.conf, today) {
+        let minutes = balance.minutes();
+        let style = match minutes {
+            m if m < 0 => Style::default().fg(Color::Red),
+            m if m > 0 => Style::default().fg(Color::Green),
+            _ => Style::default(),
+        };
+        let p = Paragraph::new(Spans::from(vec![
+            Span::raw(format!("{} ", I18n::flex_label(&app.conf.lang))),
+            Span::styled(flex::format_balance(minutes, &app.conf), style),
+        ]))
+        .block(Block::default().borders(Borders::ALL));
+        f.render_widget(p, totals_layout[8]);
+    }
+}
+
+/* Green once a target is met, red when it's still short on or after `due`, the day
+ * it should be met by. Days ahead are left uncoloured until they come.
+ */
+pub fn target_style(minutes: u32, target: u32, due: Option<NaiveDate>) -> Style {
+    let is_due = due
+        .map(|d| d <= Local::now().naive_local().date())
+        .unwrap_or(false);
+    if target == 0 {
+        Style::default()
+    } else if minutes >= target {
+        Style::default().fg(Color::Green)
+    } else if is_due {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    }
+}